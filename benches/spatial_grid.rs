@@ -0,0 +1,102 @@
+// Compares brute force against the spatial grid at increasing body counts, for planet gravity
+// (few sources, long range) and flocking-style neighbor lookups (every body, short range).
+// Run with `cargo bench --bench spatial_grid`.
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use jumpy::{gravity::GravityConfig, spatial::SpatialGrid};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const NUM_PLANETS: usize = 20;
+const WORLD_HALF_EXTENT: f32 = 1_000.0;
+const NEIGHBOR_RADIUS: f32 = 25.0;
+const ITERATIONS: u32 = 10;
+
+fn random_position(rng: &mut StdRng) -> Vec3 {
+    Vec3::new(
+        rng.gen_range(-WORLD_HALF_EXTENT..WORLD_HALF_EXTENT),
+        0.0,
+        rng.gen_range(-WORLD_HALF_EXTENT..WORLD_HALF_EXTENT),
+    )
+}
+
+fn time_per_iteration(mut f: impl FnMut() -> Vec3) -> Duration {
+    let start = Instant::now();
+    let mut total = Vec3::ZERO;
+    for _ in 0..ITERATIONS {
+        total += f();
+    }
+    // Keep the work from being optimized away
+    assert!(total.is_finite());
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let config = GravityConfig::default();
+    let mut rng = StdRng::seed_from_u64(0);
+    let planets: Vec<Vec3> = (0..NUM_PLANETS)
+        .map(|_| random_position(&mut rng))
+        .collect();
+
+    println!("           gravity                    neighbors");
+    println!("bodies     brute force   grid         brute force   grid");
+    for num_bodies in [1_000, 5_000, 10_000] {
+        let bodies: Vec<Vec3> = (0..num_bodies).map(|_| random_position(&mut rng)).collect();
+
+        let brute_force = time_per_iteration(|| {
+            let mut total = Vec3::ZERO;
+            for &planet in &planets {
+                for &body in &bodies {
+                    total += config.force_between(planet, 1e9, body, 1.0);
+                }
+            }
+            total
+        });
+
+        let mut grid = SpatialGrid::default();
+        let with_grid = time_per_iteration(|| {
+            grid.clear();
+            for (i, &body) in bodies.iter().enumerate() {
+                grid.insert(Entity::from_raw(i as u32), body);
+            }
+            let mut total = Vec3::ZERO;
+            for &planet in &planets {
+                for (_, body) in grid.within(planet, config.max_range) {
+                    total += config.force_between(planet, 1e9, body, 1.0);
+                }
+            }
+            total
+        });
+
+        let neighbors_brute_force = time_per_iteration(|| {
+            let mut total = Vec3::ZERO;
+            for &body in &bodies {
+                for &other in &bodies {
+                    if body.distance_squared(other) <= NEIGHBOR_RADIUS * NEIGHBOR_RADIUS {
+                        total += other - body;
+                    }
+                }
+            }
+            total
+        });
+
+        let neighbors_with_grid = time_per_iteration(|| {
+            grid.clear();
+            for (i, &body) in bodies.iter().enumerate() {
+                grid.insert(Entity::from_raw(i as u32), body);
+            }
+            let mut total = Vec3::ZERO;
+            for &body in &bodies {
+                for (_, other) in grid.within(body, NEIGHBOR_RADIUS) {
+                    total += other - body;
+                }
+            }
+            total
+        });
+
+        println!(
+            "{:>6}     {:>11.2?}   {:>11.2?}  {:>11.2?}   {:>11.2?}",
+            num_bodies, brute_force, with_grid, neighbors_brute_force, neighbors_with_grid
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    behaviors::{BehaviorGraph, WalkParams, WalkType},
+    damage::{DamageKind, Resistances},
+    enemy_combat::CombatStats,
+    shield::ShieldStats,
+};
+
+// A stat that is either the same for every entity, or rolled per entity from `(min, max)`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(untagged)]
+pub enum Stat {
+    Fixed(f32),
+    Between(f32, f32),
+}
+
+impl Stat {
+    pub fn sample(self, rng: &mut impl Rng) -> f32 {
+        match self {
+            Stat::Fixed(value) => value,
+            Stat::Between(min, max) if min < max => rng.gen_range(min..max),
+            Stat::Between(min, _) => min,
+        }
+    }
+
+    // For entities that are never randomized, e.g. the player's own ship
+    pub fn value(self) -> f32 {
+        match self {
+            Stat::Fixed(value) => value,
+            Stat::Between(min, max) => (min + max) / 2.0,
+        }
+    }
+
+    // Whether every value this stat can roll is finite and above zero
+    fn is_positive(self) -> bool {
+        self.all(|value| value > 0.0)
+    }
+
+    fn is_non_negative(self) -> bool {
+        self.all(|value| value >= 0.0)
+    }
+
+    fn all(self, check: impl Fn(f32) -> bool) -> bool {
+        let valid = |value: f32| value.is_finite() && check(value);
+        match self {
+            Stat::Fixed(value) => valid(value),
+            Stat::Between(min, max) => valid(min) && valid(max),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    Ball(f32),
+    // Half extents
+    Cuboid(f32, f32, f32),
+    CapsuleZ { half_height: f32, radius: f32 },
+}
+
+impl ColliderShape {
+    pub fn collider(self) -> Collider {
+        match self {
+            ColliderShape::Ball(radius) => Collider::ball(radius),
+            ColliderShape::Cuboid(x, y, z) => Collider::cuboid(x, y, z),
+            ColliderShape::CapsuleZ {
+                half_height,
+                radius,
+            } => Collider::capsule_z(half_height, radius),
+        }
+    }
+}
+
+// Balance stats for one kind of entity, read from `assets/archetypes/<kind>.ron`.
+// Stats that don't apply to a kind are left out of its file and default to zero.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct EntityArchetype {
+    pub model: String,
+    pub collider: ColliderShape,
+    pub density: Stat,
+    pub health: f32,
+    // Dealt by a hit at 10 units/s, and proportionally more or less at other speeds
+    #[serde(default)]
+    pub collision_damage: f32,
+    #[serde(default)]
+    pub damage_kind: DamageKind,
+    #[serde(default)]
+    pub resistances: Resistances,
+    // Walk for entities without a `behavior` graph. `None` picks a random walk per entity.
+    #[serde(default)]
+    pub walk_type: Option<WalkType>,
+    #[serde(default)]
+    pub walk: WalkParams,
+    // `None` just walks and drifts toward the player
+    #[serde(default)]
+    pub behavior: Option<BehaviorGraph>,
+    pub scale: Stat,
+    #[serde(default)]
+    pub speed: f32,
+    #[serde(default)]
+    pub rotation_speed: f32,
+    #[serde(default)]
+    pub roll_speed: f32,
+    #[serde(default)]
+    pub shield: Option<ShieldStats>,
+    // `None` never shoots back. Only a `behavior` graph decides when to fire, so this needs one.
+    #[serde(default)]
+    pub combat: Option<CombatStats>,
+}
+
+impl EntityArchetype {
+    // Every value that is out of range, described for the log
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if !self.density.is_positive() {
+            problems.push(format!("density must be positive, got {:?}", self.density));
+        }
+        if !self.scale.is_positive() {
+            problems.push(format!("scale must be positive, got {:?}", self.scale));
+        }
+        if !(self.health.is_finite() && self.health > 0.0) {
+            problems.push(format!("health must be positive, got {}", self.health));
+        }
+        // A negative frequency makes the Ornstein-Uhlenbeck walk's noise NaN
+        if !self.walk.amplitude.is_non_negative() {
+            problems.push(format!(
+                "walk amplitude must not be negative, got {:?}",
+                self.walk.amplitude
+            ));
+        }
+        if !self.walk.frequency.is_non_negative() {
+            problems.push(format!(
+                "walk frequency must not be negative, got {:?}",
+                self.walk.frequency
+            ));
+        }
+        if self.combat.is_some() && self.behavior.is_none() {
+            problems.push("combat needs a behavior graph to drive it".to_string());
+        }
+        problems
+    }
+}
+
+// Also tags every spawned body, so e.g. a kill can be credited to the right kind of entity
+#[derive(Component, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchetypeKind {
+    Spaceship,
+    Missile,
+    Asteroid,
+    Enemy,
+    Planet,
+}
+
+impl ArchetypeKind {
+    pub const ALL: [ArchetypeKind; 5] = [
+        ArchetypeKind::Spaceship,
+        ArchetypeKind::Missile,
+        ArchetypeKind::Asteroid,
+        ArchetypeKind::Enemy,
+        ArchetypeKind::Planet,
+    ];
+
+    pub fn path(self) -> &'static str {
+        match self {
+            ArchetypeKind::Spaceship => "archetypes/spaceship.ron",
+            ArchetypeKind::Missile => "archetypes/missile.ron",
+            ArchetypeKind::Asteroid => "archetypes/asteroid.ron",
+            ArchetypeKind::Enemy => "archetypes/enemy.ron",
+            ArchetypeKind::Planet => "archetypes/planet.ron",
+        }
+    }
+
+    // The shipped file, so there are sane stats before the asset server catches up
+    // (and in the headless app, which never loads assets)
+    fn embedded(self) -> &'static str {
+        match self {
+            ArchetypeKind::Spaceship => include_str!("../assets/archetypes/spaceship.ron"),
+            ArchetypeKind::Missile => include_str!("../assets/archetypes/missile.ron"),
+            ArchetypeKind::Asteroid => include_str!("../assets/archetypes/asteroid.ron"),
+            ArchetypeKind::Enemy => include_str!("../assets/archetypes/enemy.ron"),
+            ArchetypeKind::Planet => include_str!("../assets/archetypes/planet.ron"),
+        }
+    }
+}
+
+// The archetypes spawn functions actually build from. Starts out as the embedded copies and is
+// overwritten whenever the asset files finish loading.
+#[derive(Resource, Debug, Clone)]
+pub struct Archetypes {
+    pub spaceship: EntityArchetype,
+    pub missile: EntityArchetype,
+    pub asteroid: EntityArchetype,
+    pub enemy: EntityArchetype,
+    pub planet: EntityArchetype,
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        let embedded = |kind: ArchetypeKind| {
+            ron::from_str(kind.embedded())
+                .unwrap_or_else(|err| panic!("Invalid embedded {}: {}", kind.path(), err))
+        };
+        Self {
+            spaceship: embedded(ArchetypeKind::Spaceship),
+            missile: embedded(ArchetypeKind::Missile),
+            asteroid: embedded(ArchetypeKind::Asteroid),
+            enemy: embedded(ArchetypeKind::Enemy),
+            planet: embedded(ArchetypeKind::Planet),
+        }
+    }
+}
+
+impl Archetypes {
+    pub fn get(&self, kind: ArchetypeKind) -> &EntityArchetype {
+        match kind {
+            ArchetypeKind::Spaceship => &self.spaceship,
+            ArchetypeKind::Missile => &self.missile,
+            ArchetypeKind::Asteroid => &self.asteroid,
+            ArchetypeKind::Enemy => &self.enemy,
+            ArchetypeKind::Planet => &self.planet,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: ArchetypeKind) -> &mut EntityArchetype {
+        match kind {
+            ArchetypeKind::Spaceship => &mut self.spaceship,
+            ArchetypeKind::Missile => &mut self.missile,
+            ArchetypeKind::Asteroid => &mut self.asteroid,
+            ArchetypeKind::Enemy => &mut self.enemy,
+            ArchetypeKind::Planet => &mut self.planet,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_archetypes_are_valid() {
+        let archetypes = Archetypes::default();
+        for kind in ArchetypeKind::ALL {
+            assert_eq!(
+                archetypes.get(kind).validate(),
+                Vec::<String>::new(),
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_positive_stats() {
+        let mut archetype = Archetypes::default().asteroid;
+        archetype.density = Stat::Fixed(-1.0);
+        archetype.scale = Stat::Between(0.0, 2.0);
+        archetype.health = 0.0;
+        assert_eq!(archetype.validate().len(), 3);
+    }
+
+    #[test]
+    fn rejects_negative_walk_params() {
+        let mut archetype = Archetypes::default().asteroid;
+        archetype.walk.amplitude = Stat::Between(-1.0, 1.0);
+        archetype.walk.frequency = Stat::Fixed(-0.5);
+        assert_eq!(archetype.validate().len(), 2);
+        archetype.walk.amplitude = Stat::Fixed(0.0);
+        archetype.walk.frequency = Stat::Fixed(0.0);
+        assert!(archetype.validate().is_empty());
+    }
+
+    #[test]
+    fn rejects_combat_without_behavior() {
+        let mut archetype = Archetypes::default().enemy;
+        assert!(archetype.combat.is_some());
+        archetype.behavior = None;
+        assert_eq!(archetype.validate().len(), 1);
+    }
+}
@@ -1,4 +1,21 @@
-use bevy::prelude::*;
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    archetype::{ArchetypeKind, Archetypes, EntityArchetype},
+    collision_layers::LayerTable,
+    score::ScoreTable,
+    sound::AudioTable,
+    tuning::Tuning,
+    waves::WaveTable,
+    weapons::WeaponRegistry,
+};
 
 #[derive(Resource, Debug, Default)]
 pub struct SceneAssets {
@@ -9,19 +26,221 @@ pub struct SceneAssets {
     pub aliens: Handle<Scene>,
 }
 
+impl SceneAssets {
+    fn get_mut(&mut self, kind: ArchetypeKind) -> &mut Handle<Scene> {
+        match kind {
+            ArchetypeKind::Spaceship => &mut self.spaceship,
+            ArchetypeKind::Missile => &mut self.missiles,
+            ArchetypeKind::Asteroid => &mut self.asteroids,
+            ArchetypeKind::Enemy => &mut self.aliens,
+            ArchetypeKind::Planet => &mut self.planets,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct ArchetypeAssets {
+    pub handles: HashMap<ArchetypeKind, Handle<EntityArchetype>>,
+}
+
+// Loads any `Deserialize` asset straight from a RON file. Several of these can share the `ron`
+// extension, because assets are always loaded with their type known.
+pub struct RonAssetLoader<A> {
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<A, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+// A resource mirrored from a RON file under `assets/`. The shipped copy is compiled in as the
+// default, and edits to the file are applied while the game runs.
+pub trait RonResource: Asset + Resource + Default + Clone + PartialEq + DeserializeOwned {
+    const PATH: &'static str;
+
+    // Every value that is out of range, described for the log
+    fn validate(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+pub fn parse_embedded<T: RonResource>(text: &str) -> T {
+    ron::from_str(text).unwrap_or_else(|err| panic!("Invalid embedded {}: {}", T::PATH, err))
+}
+
+#[derive(Resource, Debug)]
+struct RonResourceHandle<T: Asset>(Handle<T>);
+
+impl<T: Asset> Default for RonResourceHandle<T> {
+    fn default() -> Self {
+        Self(Handle::default())
+    }
+}
+
+pub struct RonResourcePlugin<T> {
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for RonResourcePlugin<T> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: RonResource> Plugin for RonResourcePlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<T>()
+            .init_resource::<RonResourceHandle<T>>()
+            .init_asset::<T>()
+            .register_asset_loader(RonAssetLoader::<T>::default())
+            .add_systems(Startup, load_ron_resource::<T>)
+            .add_systems(Update, apply_ron_resource_changes::<T>);
+    }
+}
+
+fn load_ron_resource<T: RonResource>(
+    mut handle: ResMut<RonResourceHandle<T>>,
+    asset_server: Res<AssetServer>,
+) {
+    handle.0 = asset_server.load(T::PATH);
+}
+
+// A file with any bad value is rejected as a whole, so the game never runs on half an edit
+fn apply_ron_resource_changes<T: RonResource>(
+    mut asset_events: EventReader<AssetEvent<T>>,
+    handle: Res<RonResourceHandle<T>>,
+    loaded_assets: Res<Assets<T>>,
+    mut resource: ResMut<T>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        let Some(loaded) = loaded_assets.get(*id) else {
+            continue;
+        };
+        let problems = loaded.validate();
+        if !problems.is_empty() {
+            for problem in problems {
+                error!("{}: {}", T::PATH, problem);
+            }
+            warn!("Keeping the previous values until {} is fixed", T::PATH);
+            continue;
+        }
+        // Only touch the resource on a real change, so change detection stays meaningful
+        if *resource != *loaded {
+            *resource = loaded.clone();
+            info!("Applied {}", T::PATH);
+        }
+    }
+}
+
 pub struct AssetLoaderPlugin;
 
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SceneAssets>()
-            .add_systems(Startup, load_assets);
+            .init_resource::<ArchetypeAssets>()
+            .init_resource::<Archetypes>()
+            .init_asset::<EntityArchetype>()
+            .register_asset_loader(RonAssetLoader::<EntityArchetype>::default())
+            .add_plugins((
+                RonResourcePlugin::<Tuning>::default(),
+                RonResourcePlugin::<WeaponRegistry>::default(),
+                RonResourcePlugin::<LayerTable>::default(),
+                RonResourcePlugin::<WaveTable>::default(),
+                RonResourcePlugin::<ScoreTable>::default(),
+                RonResourcePlugin::<AudioTable>::default(),
+            ))
+            .add_systems(Startup, load_assets)
+            .add_systems(Update, apply_archetype_changes);
     }
 }
 
-fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
-    scene_assets.asteroids = asset_server.load("models/Rock.glb#Scene0");
-    scene_assets.spaceship = asset_server.load("models/Spaceship.glb#Scene0");
-    scene_assets.missiles = asset_server.load("models/Bush.glb#Scene0");
-    scene_assets.planets = asset_server.load("models/Planet.glb#Scene0");
-    scene_assets.aliens = asset_server.load("models/Mech.glb#Scene0");
+fn load_assets(
+    mut scene_assets: ResMut<SceneAssets>,
+    mut archetype_assets: ResMut<ArchetypeAssets>,
+    archetypes: Res<Archetypes>,
+    asset_server: Res<AssetServer>,
+) {
+    for kind in ArchetypeKind::ALL {
+        *scene_assets.get_mut(kind) = asset_server.load(archetypes.get(kind).model.clone());
+        archetype_assets
+            .handles
+            .insert(kind, asset_server.load(kind.path()));
+    }
+}
+
+// Swaps in archetype files as they load, so designers' edits take effect without a rebuild.
+// Like the other RON files, one with a bad value is rejected as a whole.
+fn apply_archetype_changes(
+    mut asset_events: EventReader<AssetEvent<EntityArchetype>>,
+    archetype_assets: Res<ArchetypeAssets>,
+    entity_archetypes: Res<Assets<EntityArchetype>>,
+    mut archetypes: ResMut<Archetypes>,
+    mut scene_assets: ResMut<SceneAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        let Some((&kind, _)) = archetype_assets
+            .handles
+            .iter()
+            .find(|(_, handle)| handle.id() == *id)
+        else {
+            continue;
+        };
+        let Some(archetype) = entity_archetypes.get(*id) else {
+            continue;
+        };
+        let problems = archetype.validate();
+        if !problems.is_empty() {
+            for problem in problems {
+                error!("{}: {}", kind.path(), problem);
+            }
+            warn!("Keeping the previous values until {} is fixed", kind.path());
+            continue;
+        }
+        if archetypes.get(kind).model != archetype.model {
+            *scene_assets.get_mut(kind) = asset_server.load(archetype.model.clone());
+        }
+        *archetypes.get_mut(kind) = archetype.clone();
+        info!("Applied {}", kind.path());
+    }
 }
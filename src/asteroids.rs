@@ -1,24 +1,23 @@
 use std::f32::consts::PI;
 use std::ops::Range;
+use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use rand::Rng;
 
+use crate::archetype::{ArchetypeKind, Archetypes};
 use crate::asset_loader::SceneAssets;
 use crate::collision_detection::CollisionDamage;
+use crate::collision_layers::{CollisionLayer, LayerTable};
 use crate::debug::DebugEntity;
 use crate::health::Health;
+use crate::rng::{GameRng, RngStream};
 use crate::schedule::InGameSet;
 use crate::spaceship::Spaceship;
+use crate::state::{RunReset, RunResetSet};
+use crate::tuning::Tuning;
 
-const MASS_SCALAR: f32 = 3.0;
-const VELOCITY_SCALAR: f32 = 5.0;
-const SPAWN_TIME_SECONDS: f32 = 1.0;
-const ROTATION_SPEED: f32 = 2.5;
-const HEALTH: f32 = 80.0;
-const COLLISION_DAMAGE: f32 = 35.0;
-const SCALE: Vec3 = Vec3::splat(1.);
 const MAX_NUM_ASTEROIDS: usize = 1_000;
 const SPAWN_RANGE: Range<f32> = 100.0..500.0;
 
@@ -30,20 +29,51 @@ pub struct SpawnTimer {
     pub timer: Timer,
 }
 
+impl SpawnTimer {
+    fn new(tuning: &Tuning) -> Self {
+        Self {
+            timer: Timer::from_seconds(tuning.asteroid_spawn_seconds, TimerMode::Repeating),
+        }
+    }
+}
+
+impl FromWorld for SpawnTimer {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(&world.get_resource_or_insert_with(Tuning::default))
+    }
+}
+
 pub struct AsteroidPlugin;
 
 impl Plugin for AsteroidPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SpawnTimer {
-            timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
-        })
-        .add_systems(
-            Update,
-            ((spawn_asteroids).in_set(InGameSet::EntityUpdates),),
-        );
+        app.init_resource::<SpawnTimer>()
+            .add_systems(
+                FixedUpdate,
+                ((
+                    retime_spawns.run_if(resource_changed::<Tuning>),
+                    spawn_asteroids,
+                )
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),),
+            )
+            .add_systems(RunReset, reset_spawn_timer.in_set(RunResetSet::Reset));
     }
 }
 
+// So a retry on the same seed spawns asteroids on the same steps as the run before it
+fn reset_spawn_timer(mut spawn_timer: ResMut<SpawnTimer>, tuning: Res<Tuning>) {
+    *spawn_timer = SpawnTimer::new(&tuning);
+}
+
+fn retime_spawns(mut spawn_timer: ResMut<SpawnTimer>, tuning: Res<Tuning>) {
+    let duration = Duration::from_secs_f32(tuning.asteroid_spawn_seconds);
+    if spawn_timer.timer.duration() != duration {
+        spawn_timer.timer.set_duration(duration);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_asteroids(
     mut commands: Commands,
     astroid_query: Query<&Transform, With<Asteroid>>,
@@ -51,40 +81,55 @@ fn spawn_asteroids(
     mut spawn_timer: ResMut<SpawnTimer>,
     time: Res<Time>,
     scene_assets: Res<SceneAssets>,
+    archetypes: Res<Archetypes>,
+    layer_table: Res<LayerTable>,
+    mut game_rng: ResMut<GameRng>,
 ) {
     spawn_timer.timer.tick(time.delta());
     if !spawn_timer.timer.just_finished() {
         return;
     }
+    // The ship can be despawned earlier in the same step, before the run ends
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
     let num_asteroids = astroid_query.iter().len();
     if num_asteroids >= MAX_NUM_ASTEROIDS {
         return;
     }
     let astroid_spawn_count = (MAX_NUM_ASTEROIDS - num_asteroids).min(MAX_NUM_ASTEROIDS);
     info!("Spawning {} asteroids", astroid_spawn_count);
-    let player_pos = player_query.single().translation;
+    let player_pos = player_transform.translation;
+    let rng = game_rng.stream(RngStream::Asteroids);
+    let archetype = &archetypes.asteroid;
 
     for _ in 0..astroid_spawn_count {
-        let (x, z) = get_random_position_around(player_pos, SPAWN_RANGE);
+        let (x, z) = get_random_position_around(rng, player_pos, SPAWN_RANGE);
         let translation = Vec3::new(x, 0.0, z);
 
-        let velocity = get_random_unit_vector() * VELOCITY_SCALAR;
+        let velocity = get_random_unit_vector(rng) * archetype.speed;
+        let scale = archetype.scale.sample(rng);
 
         commands.spawn((
             SceneBundle {
                 scene: scene_assets.asteroids.clone(),
-                transform: Transform::from_translation(translation).with_scale(SCALE),
+                transform: Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
                 ..default()
             },
-            Collider::ball(1.0),
-            ColliderMassProperties::Density(MASS_SCALAR),
+            archetype.collider.collider(),
+            ColliderMassProperties::Density(archetype.density.sample(rng)),
+            ReadMassProperties::default(),
             Velocity {
                 linvel: velocity,
-                angvel: Vec3::splat(ROTATION_SPEED),
+                angvel: Vec3::splat(archetype.rotation_speed),
             },
             Asteroid,
-            Health::new(HEALTH),
-            CollisionDamage::new(COLLISION_DAMAGE),
+            ArchetypeKind::Asteroid,
+            layer_table.bundle(CollisionLayer::Environment),
+            Health::new(archetype.health),
+            CollisionDamage::new(archetype.collision_damage, archetype.damage_kind),
+            archetype.resistances,
+            ActiveEvents::COLLISION_EVENTS,
             DebugEntity,
             RigidBody::Dynamic,
             ExternalForce::default(),
@@ -92,8 +137,7 @@ fn spawn_asteroids(
     }
 }
 
-pub fn get_random_position_around(pos: Vec3, range: Range<f32>) -> (f32, f32) {
-    let mut rng = rand::thread_rng();
+pub fn get_random_position_around(rng: &mut impl Rng, pos: Vec3, range: Range<f32>) -> (f32, f32) {
     let angle = rng.gen_range(0.0..PI * 2.0);
     let dist = rng.gen_range(range);
 
@@ -106,7 +150,6 @@ pub fn get_random_position_around(pos: Vec3, range: Range<f32>) -> (f32, f32) {
     (random_x, random_y)
 }
 
-fn get_random_unit_vector() -> Vec3 {
-    let mut rng = rand::thread_rng();
+fn get_random_unit_vector(rng: &mut impl Rng) -> Vec3 {
     Vec3::new(rng.gen_range(-1.0..1.0), 0.0, rng.gen_range(-1.0..1.0)).normalize_or_zero()
 }
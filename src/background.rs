@@ -12,10 +12,9 @@ use noise::{
     utils::{ColorGradient, NoiseMapBuilder, PlaneMapBuilder},
     Add, Cache, Fbm, MultiFractal, NoiseFn, Perlin, Worley,
 };
-use rand::Rng;
 
-const WIDTH: u32 = 1000;
-const HEIGHT: u32 = 6000;
+// The cubemap is stored as its faces stacked on top of each other
+const FACES: u32 = 6;
 const NEBULA_FREQUENCY: f64 = 2.0;
 const NEBULA_OCATAVES: usize = 5;
 const STAR_FREQUENCY: f64 = 1.0;
@@ -27,10 +26,11 @@ const Y_RANGE: Range<f64> = -30.0..30.0;
 #[derive(Component)]
 pub struct Background;
 
-pub fn build_image(mut images: ResMut<Assets<Image>>) -> Handle<Image> {
+// The same `seed` gives the same sky at any `face_size`
+pub fn build_image(images: &mut Assets<Image>, seed: u32, face_size: u32) -> Handle<Image> {
     let size = Extent3d {
-        width: WIDTH,
-        height: HEIGHT,
+        width: face_size,
+        height: face_size * FACES,
         ..default()
     };
     let mut image = Image {
@@ -52,9 +52,7 @@ pub fn build_image(mut images: ResMut<Assets<Image>>) -> Handle<Image> {
     // fill image.data with zeroes
     image.resize(size);
 
-    fn nebula_noise() -> impl NoiseFn<f64, 3> {
-        let mut rng = rand::thread_rng();
-        let seed = rng.gen_range(0..u32::MAX);
+    fn nebula_noise(seed: u32) -> impl NoiseFn<f64, 3> {
         let nebula_base = Fbm::<Perlin>::new(seed)
             .set_frequency(NEBULA_FREQUENCY)
             .set_persistence(NEBULA_PERSISTENCE)
@@ -68,7 +66,7 @@ pub fn build_image(mut images: ResMut<Assets<Image>>) -> Handle<Image> {
         let nebula = Add::new(nebula_base, stars);
         Cache::new(nebula)
     }
-    let nebula_noise = nebula_noise();
+    let nebula_noise = nebula_noise(seed);
 
     let noise_map = PlaneMapBuilder::new(&nebula_noise)
         .set_x_bounds(X_RANGE.start, X_RANGE.end)
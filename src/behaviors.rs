@@ -1,56 +1,328 @@
+use std::f32::consts::TAU;
+
 use bevy::prelude::*;
-use bevy_rapier3d::dynamics::ExternalForce;
+use bevy_rapier3d::prelude::*;
 use noise::{NoiseFn, Perlin};
 use rand::distributions::{Distribution, Uniform};
 use rand::{distributions::Standard, Rng};
-use rand_distr::Normal;
+use rand_distr::{Normal, Pareto, StandardNormal};
+use serde::Deserialize;
 
+use crate::archetype::Stat;
+use crate::enemy_combat::EnemyCombat;
+use crate::health::Health;
+use crate::planet::Planet;
+use crate::rng::{GameRng, RngStream};
 use crate::schedule::InGameSet;
-use crate::spaceship::Spaceship;
+use crate::spaceship::{Spaceship, SpaceshipMissile};
+use crate::spatial::SpatialGrid;
+use crate::steering::{self, flatten, SteeringLimits};
 
-const SEEK_SPEED: f32 = 0.25;
+// For walkers without a behavior graph, which drift toward the player on top of their walk
+const SEEK_LIMITS: SteeringLimits = SteeringLimits {
+    max_speed: 20.0,
+    max_force: 0.25,
+};
+// Walkers farther out than this are left to their walk. It reaches past the farthest that
+// asteroids (500) and wave groups (350 plus their radius) spawn, so new arrivals still close in.
+const SEEK_RANGE: f32 = 600.0;
+// Height above a planet's surface that `Behavior::Orbit` circles at
+const ORBIT_ALTITUDE: f32 = 30.0;
+// Not flying into a planet comes before anything else an entity wants
+const AVOIDANCE_WEIGHT: f32 = 2.0;
 
 pub struct BehaviorsPlugin;
 
-#[derive(Component)]
+#[derive(Component, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum WalkType {
     Random,
     Gaussian,
     Normal,
+    // Smooth noise that drifts at `frequency`
     Perlin,
+    // Straight flights in random directions, mostly short with the occasional very long one
+    LevyFlight,
+    // A random drift that keeps getting pulled back toward standing still, at rate `frequency`
+    OrnsteinUhlenbeck,
+}
+
+// How hard and how fast an archetype's entities walk. Ranges are sampled per entity.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct WalkParams {
+    pub amplitude: Stat,
+    pub frequency: Stat,
+}
+
+impl Default for WalkParams {
+    fn default() -> Self {
+        Self {
+            amplitude: Stat::Fixed(1.0),
+            frequency: Stat::Fixed(1.0),
+        }
+    }
+}
+
+// Per-entity memory, so walks that evolve over time pick up where they left off each step
+#[derive(Component, Debug)]
+pub struct WalkState {
+    pub amplitude: f32,
+    pub frequency: f32,
+    perlin: Perlin,
+    // Position along the noise, starting at a random offset
+    noise_time: f64,
+    // Ornstein-Uhlenbeck's current value
+    drift: Vec3,
+    // Current Lévy flight direction, and how long until the next turn
+    heading: Vec3,
+    flight_left: f32,
 }
 
+impl WalkState {
+    pub fn new(params: &WalkParams, rng: &mut impl Rng) -> Self {
+        Self {
+            amplitude: params.amplitude.sample(rng),
+            frequency: params.frequency.sample(rng),
+            perlin: Perlin::new(rng.gen()),
+            noise_time: rng.gen_range(0.0..1000.0),
+            drift: Vec3::ZERO,
+            heading: Vec3::ZERO,
+            flight_left: 0.0,
+        }
+    }
+}
+
+// Lévy flight lengths follow a power law with this exponent. Lower means more long flights.
+const LEVY_EXPONENT: f32 = 1.5;
+// Longest single flight, in multiples of the shortest
+const LEVY_MAX_FLIGHT: f32 = 20.0;
+
 impl WalkType {
-    pub fn walk(&self, ext_force: &mut ExternalForce, transform: &Transform) {
-        match self {
+    // Force for this step of the walk
+    pub fn walk(&self, state: &mut WalkState, rng: &mut impl Rng, delta_seconds: f32) -> Vec3 {
+        let force = match self {
             WalkType::Random => {
-                let mut rng = rand::thread_rng();
                 let dist = Uniform::new(-1.0, 1.0);
-                let (x, z) = (rng.sample(dist), rng.sample(dist));
-                ext_force.force += Vec3::new(x, 0., z);
+                Vec3::new(rng.sample(dist), 0., rng.sample(dist))
             }
             WalkType::Gaussian => {
                 let distribution = Standard;
-                let mut rng = rand::thread_rng();
-                let (x, z) = (distribution.sample(&mut rng), distribution.sample(&mut rng));
-                ext_force.force += Vec3::new(x, 0., z);
+                Vec3::new(distribution.sample(rng), 0., distribution.sample(rng))
             }
             WalkType::Normal => {
                 let distribution = Normal::new(0.0, 0.5).unwrap();
-                let mut rng = rand::thread_rng();
-                let (x, z) = (distribution.sample(&mut rng), distribution.sample(&mut rng));
-                ext_force.force += Vec3::new(x, 0., z);
+                Vec3::new(distribution.sample(rng), 0., distribution.sample(rng))
             }
             WalkType::Perlin => {
-                let mut rng = rand::thread_rng();
-                let seed = rng.gen_range(0..u32::MAX);
-                let perlin = Perlin::new(seed);
+                state.noise_time += (delta_seconds * state.frequency) as f64;
                 let (x, z) = (
-                    perlin.get([transform.translation.x as f64, 0., 0.]),
-                    perlin.get([0., 0., transform.translation.z as f64]),
+                    state.perlin.get([state.noise_time, 0.0]),
+                    state.perlin.get([state.noise_time, 100.0]),
                 );
-                ext_force.force += Vec3::new(x as f32, 0., z as f32);
+                Vec3::new(x as f32, 0., z as f32)
+            }
+            WalkType::LevyFlight => {
+                state.flight_left -= delta_seconds;
+                if state.flight_left <= 0.0 {
+                    let shortest = 1.0 / state.frequency.max(f32::EPSILON);
+                    let pareto = Pareto::new(shortest, LEVY_EXPONENT).unwrap();
+                    state.flight_left = pareto.sample(rng).min(shortest * LEVY_MAX_FLIGHT);
+                    let angle = rng.gen_range(0.0..TAU);
+                    state.heading = Vec3::new(angle.cos(), 0., angle.sin());
+                }
+                state.heading
             }
+            WalkType::OrnsteinUhlenbeck => {
+                // dX = -θX dt + σ dW, with σ chosen so each axis settles at a standard
+                // deviation of 1
+                let theta = state.frequency;
+                let sigma = (2.0 * theta).sqrt();
+                let noise = Vec3::new(rng.sample(StandardNormal), 0., rng.sample(StandardNormal));
+                state.drift +=
+                    -theta * state.drift * delta_seconds + sigma * delta_seconds.sqrt() * noise;
+                state.drift
+            }
+        };
+        force * state.amplitude
+    }
+}
+
+// One thing an AI can be doing. Each entity with a `BehaviorState` does exactly one at a time.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Behavior {
+    // Coast to a stop
+    Idle,
+    Wander(WalkType),
+    // Head straight for the player
+    Seek,
+    // Head straight away from the player
+    Flee,
+    // Circle the nearest planet
+    Orbit,
+    // Hold the weapon's engagement distance. Only attacking enemies fire.
+    Attack,
+    // Sidestep out of the path of the nearest incoming projectile
+    Evade,
+}
+
+// `Behavior` without its data, so transitions can name a state without spelling out its walk
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BehaviorKind {
+    Idle,
+    Wander,
+    Seek,
+    Flee,
+    Orbit,
+    Attack,
+    Evade,
+}
+
+impl Behavior {
+    pub fn kind(self) -> BehaviorKind {
+        match self {
+            Behavior::Idle => BehaviorKind::Idle,
+            Behavior::Wander(_) => BehaviorKind::Wander,
+            Behavior::Seek => BehaviorKind::Seek,
+            Behavior::Flee => BehaviorKind::Flee,
+            Behavior::Orbit => BehaviorKind::Orbit,
+            Behavior::Attack => BehaviorKind::Attack,
+            Behavior::Evade => BehaviorKind::Evade,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    PlayerWithin(f32),
+    PlayerBeyond(f32),
+    // Fractions of starting health
+    HealthBelow(f32),
+    HealthAbove(f32),
+    // Nothing this entity collides with stands between it and the player
+    LineOfSight,
+    NoLineOfSight,
+    // Distance to the nearest planet's surface
+    PlanetWithin(f32),
+    // A player projectile is this close
+    ThreatWithin(f32),
+    // Seconds since the current behavior started
+    TimeInState(f32),
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Transition {
+    // Empty matches every behavior
+    #[serde(default)]
+    pub from: Vec<BehaviorKind>,
+    pub to: Behavior,
+    // All of these must hold
+    pub when: Vec<Condition>,
+}
+
+// A state machine declared in the `behavior` section of an archetype
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BehaviorGraph {
+    pub initial: Behavior,
+    pub max_speed: f32,
+    // Most force the entity can put out while steering
+    pub max_force: f32,
+    // Seconds ahead to look for planets to steer around. Zero flies straight into them.
+    #[serde(default)]
+    pub planet_look_ahead: f32,
+    #[serde(default)]
+    pub flocking: Option<Flocking>,
+    // Checked in order every step, and the first one that applies is taken
+    pub transitions: Vec<Transition>,
+}
+
+// Boids weights for moving as a group with other entities that have a behavior graph
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Flocking {
+    // How far away other entities count as neighbors
+    pub radius: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+impl BehaviorGraph {
+    pub fn limits(&self) -> SteeringLimits {
+        SteeringLimits {
+            max_speed: self.max_speed,
+            max_force: self.max_force,
+        }
+    }
+
+    fn conditions(&self) -> impl Iterator<Item = Condition> + '_ {
+        self.transitions
+            .iter()
+            .flat_map(|transition| transition.when.iter().copied())
+    }
+
+    fn uses_line_of_sight(&self) -> bool {
+        self.conditions()
+            .any(|condition| matches!(condition, Condition::LineOfSight | Condition::NoLineOfSight))
+    }
+
+    // How far out projectiles are worth noticing. Zero when nothing in the graph reacts to them.
+    fn threat_range(&self) -> f32 {
+        self.conditions()
+            .filter_map(|condition| match condition {
+                Condition::ThreatWithin(distance) => Some(distance),
+                _ => None,
+            })
+            .fold(0.0, f32::max)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct BehaviorState {
+    pub graph: BehaviorGraph,
+    pub current: Behavior,
+    pub time_in_state: f32,
+    pub max_health: f32,
+}
+
+impl BehaviorState {
+    pub fn new(graph: BehaviorGraph, max_health: f32) -> Self {
+        Self {
+            current: graph.initial,
+            graph,
+            time_in_state: 0.0,
+            max_health,
+        }
+    }
+}
+
+// What an entity knows about its surroundings this step
+struct Senses {
+    player_distance: f32,
+    health_fraction: f32,
+    line_of_sight: bool,
+    // Position and surface radius
+    nearest_planet: Option<(Vec3, f32)>,
+    planet_distance: Option<f32>,
+    // Position and velocity
+    nearest_threat: Option<(Vec3, Vec3)>,
+    threat_distance: Option<f32>,
+}
+
+impl Condition {
+    fn holds(self, senses: &Senses, time_in_state: f32) -> bool {
+        match self {
+            Condition::PlayerWithin(distance) => senses.player_distance <= distance,
+            Condition::PlayerBeyond(distance) => senses.player_distance > distance,
+            Condition::HealthBelow(fraction) => senses.health_fraction < fraction,
+            Condition::HealthAbove(fraction) => senses.health_fraction > fraction,
+            Condition::LineOfSight => senses.line_of_sight,
+            Condition::NoLineOfSight => !senses.line_of_sight,
+            Condition::PlanetWithin(distance) => senses
+                .planet_distance
+                .is_some_and(|planet_distance| planet_distance <= distance),
+            Condition::ThreatWithin(distance) => senses
+                .threat_distance
+                .is_some_and(|threat_distance| threat_distance <= distance),
+            Condition::TimeInState(seconds) => time_in_state >= seconds,
         }
     }
 }
@@ -58,37 +330,261 @@ impl WalkType {
 impl Plugin for BehaviorsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            (handle_walks, handle_seek_player).in_set(InGameSet::EntityUpdates),
+            FixedUpdate,
+            (handle_walks, handle_seek_player, run_behavior_states)
+                .in_set(InGameSet::EntityUpdates),
         );
     }
 }
 
-fn handle_walks(mut walker_query: Query<(&Transform, &mut ExternalForce, &WalkType)>) {
-    for (transform, mut ext_force, walk_type) in walker_query.iter_mut() {
-        walk_type.walk(&mut ext_force, transform);
+fn handle_walks(
+    mut walker_query: Query<(&mut ExternalForce, &WalkType, &mut WalkState)>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    let rng = game_rng.stream(RngStream::Behaviors);
+    for (mut ext_force, walk_type, mut walk_state) in walker_query.iter_mut() {
+        ext_force.force += walk_type.walk(&mut walk_state, rng, time.delta_seconds());
     }
 }
 
 fn handle_seek_player(
-    mut walker_query: Query<(&Transform, &mut ExternalForce), With<WalkType>>,
+    mut walker_query: Query<(&Transform, &Velocity, &mut ExternalForce), With<WalkType>>,
     player_query: Query<&Transform, With<Spaceship>>,
+    grid: Res<SpatialGrid>,
 ) {
     let Ok(player_transform) = player_query.get_single() else {
         return;
     };
-    for (transform, mut ext_force) in walker_query.iter_mut() {
-        let direction = player_transform.translation - transform.translation;
-        ext_force.force += direction.normalize() * SEEK_SPEED;
+    for (entity, _) in grid.within(player_transform.translation, SEEK_RANGE) {
+        let Ok((transform, velocity, mut ext_force)) = walker_query.get_mut(entity) else {
+            continue;
+        };
+        ext_force.force += steering::seek(
+            transform.translation,
+            velocity.linvel,
+            player_transform.translation,
+            &SEEK_LIMITS,
+        );
+    }
+}
+
+// Senses, takes at most one transition, then acts on whichever behavior is current
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn run_behavior_states(
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &Health,
+        Option<&CollisionGroups>,
+        Option<&EnemyCombat>,
+        &mut BehaviorState,
+        &mut ExternalForce,
+        Option<&mut WalkState>,
+    )>,
+    player_query: Query<(Entity, &Transform, &Velocity), With<Spaceship>>,
+    planet_query: Query<(&Transform, &Collider), With<Planet>>,
+    flock_query: Query<&Velocity, With<BehaviorState>>,
+    threat_query: Query<&Velocity, With<SpaceshipMissile>>,
+    grid: Res<SpatialGrid>,
+    rapier_context: Res<RapierContext>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    let Ok((player, player_transform, player_velocity)) = player_query.get_single() else {
+        return;
+    };
+    // Center and surface radius of every planet
+    let planets: Vec<(Vec3, f32)> = planet_query
+        .iter()
+        .map(|(planet_transform, collider)| {
+            let radius =
+                collider.as_ball().map_or(0.0, |ball| ball.radius()) * planet_transform.scale.x;
+            (planet_transform.translation, radius)
+        })
+        .collect();
+    let rng = game_rng.stream(RngStream::Behaviors);
+    for (
+        entity,
+        transform,
+        velocity,
+        health,
+        groups,
+        combat,
+        mut state,
+        mut ext_force,
+        mut walk_state,
+    ) in query.iter_mut()
+    {
+        let position = transform.translation;
+        let to_player = flatten(player_transform.translation - position);
+        let player_distance = to_player.length();
+
+        let line_of_sight = state.graph.uses_line_of_sight() && {
+            let mut filter = QueryFilter::default().exclude_rigid_body(entity);
+            if let Some(groups) = groups {
+                filter = filter.groups(*groups);
+            }
+            to_player.try_normalize().is_some_and(|direction| {
+                rapier_context
+                    .cast_ray(position, direction, player_distance, true, filter)
+                    .is_none_or(|(hit, _)| hit == player)
+            })
+        };
+        let nearest_planet = planets
+            .iter()
+            .copied()
+            .min_by(|(a, a_radius), (b, b_radius)| {
+                (flatten(*a - position).length() - a_radius)
+                    .total_cmp(&(flatten(*b - position).length() - b_radius))
+            });
+        let threat_range = state.graph.threat_range();
+        let nearest_threat = grid
+            .within(position, threat_range)
+            .filter_map(|(threat, threat_position)| {
+                threat_query
+                    .get(threat)
+                    .ok()
+                    .map(|threat_velocity| (threat_position, threat_velocity.linvel))
+            })
+            .min_by(|(a, _), (b, _)| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+        let senses = Senses {
+            player_distance,
+            health_fraction: health.value / state.max_health.max(f32::EPSILON),
+            line_of_sight,
+            nearest_planet,
+            planet_distance: nearest_planet
+                .map(|(center, radius)| flatten(center - position).length() - radius),
+            nearest_threat,
+            threat_distance: nearest_threat
+                .map(|(threat_position, _)| flatten(threat_position - position).length()),
+        };
+
+        state.time_in_state += time.delta_seconds();
+        let current = state.current;
+        let next = state
+            .graph
+            .transitions
+            .iter()
+            .filter(|transition| transition.to != current)
+            .filter(|transition| {
+                transition.from.is_empty() || transition.from.contains(&current.kind())
+            })
+            .find(|transition| {
+                transition
+                    .when
+                    .iter()
+                    .all(|condition| condition.holds(&senses, state.time_in_state))
+            })
+            .map(|transition| transition.to);
+        if let Some(next) = next {
+            debug!("{:?} switched from {:?} to {:?}", entity, current, next);
+            state.current = next;
+            state.time_in_state = 0.0;
+        }
+
+        let limits = state.graph.limits();
+        let linvel = velocity.linvel;
+        let player_position = player_transform.translation;
+        let mut force = match state.current {
+            Behavior::Idle => limits.toward_velocity(linvel, Vec3::ZERO),
+            Behavior::Wander(walk_type) => walk_state.as_mut().map_or(Vec3::ZERO, |walk_state| {
+                walk_type.walk(walk_state, rng, time.delta_seconds())
+            }),
+            Behavior::Seek => steering::pursue(
+                position,
+                linvel,
+                player_position,
+                player_velocity.linvel,
+                &limits,
+            ),
+            Behavior::Flee => steering::evade(
+                position,
+                linvel,
+                player_position,
+                player_velocity.linvel,
+                &limits,
+            ),
+            Behavior::Orbit => match senses.nearest_planet {
+                Some((center, radius)) => {
+                    let outward = flatten(position - center);
+                    let orbit_radius = radius + ORBIT_ALTITUDE;
+                    let correction =
+                        ((orbit_radius - outward.length()) / orbit_radius).clamp(-1.0, 1.0);
+                    let outward = outward.normalize_or_zero();
+                    let desired = (Vec3::Y.cross(outward) + outward * correction)
+                        .normalize_or_zero()
+                        * limits.max_speed;
+                    limits.toward_velocity(linvel, desired)
+                }
+                None => Vec3::ZERO,
+            },
+            Behavior::Attack => match combat {
+                // Hold station on this side of the player, `engage_distance` out
+                Some(combat) => {
+                    let engage_distance = combat.stats.engage_distance;
+                    let station = player_position - to_player.normalize_or_zero() * engage_distance;
+                    steering::arrive(position, linvel, station, engage_distance * 0.5, &limits)
+                }
+                None => steering::pursue(
+                    position,
+                    linvel,
+                    player_position,
+                    player_velocity.linvel,
+                    &limits,
+                ),
+            },
+            Behavior::Evade => match senses.nearest_threat {
+                // Away from the line the projectile is travelling along
+                Some((threat_position, threat_velocity)) => {
+                    let offset = flatten(position - threat_position);
+                    let heading = flatten(threat_velocity).normalize_or_zero();
+                    let sideways = (offset - heading * offset.dot(heading))
+                        .try_normalize()
+                        .unwrap_or_else(|| Vec3::Y.cross(heading));
+                    limits.toward_velocity(linvel, sideways * limits.max_speed)
+                }
+                None => Vec3::ZERO,
+            },
+        };
+        if let Some(flocking) = state.graph.flocking {
+            let neighbors: Vec<(Vec3, Vec3)> = grid
+                .within(position, flocking.radius)
+                .filter(|(neighbor, _)| *neighbor != entity)
+                .filter_map(|(neighbor, neighbor_position)| {
+                    flock_query
+                        .get(neighbor)
+                        .ok()
+                        .map(|neighbor_velocity| (neighbor_position, neighbor_velocity.linvel))
+                })
+                .collect();
+            force += steering::separation(position, linvel, &neighbors, &limits)
+                * flocking.separation
+                + steering::alignment(linvel, &neighbors, &limits) * flocking.alignment
+                + steering::cohesion(position, linvel, &neighbors, &limits) * flocking.cohesion;
+        }
+        force += steering::avoid_obstacles(
+            position,
+            linvel,
+            planets.iter().copied(),
+            state.graph.planet_look_ahead,
+            &limits,
+        ) * AVOIDANCE_WEIGHT;
+        ext_force.force += limits.limit(force);
     }
 }
 
-pub fn get_random_walk_type() -> WalkType {
-    let mut rng = rand::thread_rng();
-    match rng.gen_range(0..4) {
+pub fn get_random_walk_type(rng: &mut impl Rng) -> WalkType {
+    match rng.gen_range(0..6) {
         0 => WalkType::Random,
         1 => WalkType::Gaussian,
         2 => WalkType::Normal,
-        _ => WalkType::Perlin,
+        3 => WalkType::Perlin,
+        4 => WalkType::LevyFlight,
+        _ => WalkType::OrnsteinUhlenbeck,
     }
 }
@@ -4,49 +4,128 @@ use bevy::{
     prelude::*,
 };
 
-use crate::{background::build_image, post_processing::PostProcessSettings, spaceship::Spaceship};
-
-const CAMERA_DISTANCE_INIT: f32 = 120.0;
-const CAMERA_LERP_SPEED: f32 = 2.;
-const CAMERA_SCROLL_FACTOR: f32 = 70.0;
+use crate::{
+    background::build_image,
+    physics::TransformInterpolationSet,
+    post_processing::PostProcessSettings,
+    rng::{GameRng, RngStream},
+    settings::Settings,
+    spaceship::Spaceship,
+    tuning::Tuning,
+};
+use rand::Rng;
 
 #[derive(Component)]
 pub struct MainCamera;
 
+// Kept so a quality change can rebuild the same sky at another resolution
+#[derive(Resource)]
+struct SkyboxSeed(u32);
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_camera).add_systems(
             Update,
-            (pan_camera_to_spaceship, zoom_camera_controls).chain(),
+            (
+                apply_display_quality.run_if(resource_changed::<Settings>),
+                reset_camera_distance.run_if(resource_changed::<Tuning>),
+                pan_camera_to_spaceship,
+                zoom_camera_controls,
+            )
+                .chain()
+                .after(TransformInterpolationSet),
         );
     }
 }
 
-fn spawn_camera(mut commands: Commands, images: ResMut<Assets<Image>>) {
-    commands.spawn((
+fn spawn_camera(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut game_rng: ResMut<GameRng>,
+    tuning: Res<Tuning>,
+    settings: Res<Settings>,
+) {
+    let profile = settings.display_quality.profile();
+    let seed = game_rng
+        .stream(RngStream::Background)
+        .gen_range(0..u32::MAX);
+    let mut camera = commands.spawn((
         Camera3dBundle {
             camera: Camera::default(),
             tonemapping: Tonemapping::TonyMcMapface,
-            transform: Transform::from_xyz(0.0, CAMERA_DISTANCE_INIT, 0.0)
+            transform: Transform::from_xyz(0.0, tuning.camera.distance_init, 0.0)
                 .looking_at(Vec3::ZERO, Vec3::Z),
             ..default()
         },
         MainCamera,
-        BloomSettings::NATURAL,
-        PostProcessSettings { intensity: 0.002 },
         Skybox {
-            image: build_image(images).clone(),
+            image: build_image(&mut images, seed, profile.skybox_size),
             brightness: 1000.0,
         },
     ));
+    if let Some(bloom) = profile.bloom {
+        camera.insert(bloom);
+    }
+    if profile.post_process {
+        camera.insert(PostProcessSettings {
+            intensity: tuning.post_process_intensity,
+        });
+    }
+    commands.insert_resource(SkyboxSeed(seed));
+}
+
+// Bloom and the post-process pass only run on cameras that carry their settings component
+fn apply_display_quality(
+    mut commands: Commands,
+    mut camera_query: Query<(Entity, &mut Skybox), With<MainCamera>>,
+    mut images: ResMut<Assets<Image>>,
+    seed: Res<SkyboxSeed>,
+    settings: Res<Settings>,
+    tuning: Res<Tuning>,
+) {
+    let profile = settings.display_quality.profile();
+    for (camera, mut skybox) in camera_query.iter_mut() {
+        let mut camera = commands.entity(camera);
+        match profile.bloom.clone() {
+            Some(bloom) => camera.insert(bloom),
+            None => camera.remove::<BloomSettings>(),
+        };
+        if profile.post_process {
+            camera.insert(PostProcessSettings {
+                intensity: tuning.post_process_intensity,
+            });
+        } else {
+            camera.remove::<PostProcessSettings>();
+        }
+        let face_size = images.get(&skybox.image).map(|image| image.width());
+        if face_size != Some(profile.skybox_size) {
+            skybox.image = build_image(&mut images, seed.0, profile.skybox_size);
+        }
+    }
+}
+
+// Snaps the zoom back to the starting height whenever that height is retuned
+fn reset_camera_distance(
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    tuning: Res<Tuning>,
+    mut applied_distance: Local<f32>,
+) {
+    if *applied_distance == tuning.camera.distance_init {
+        return;
+    }
+    *applied_distance = tuning.camera.distance_init;
+    for mut camera_transform in camera_query.iter_mut() {
+        camera_transform.translation.y = tuning.camera.distance_init;
+    }
 }
 
 fn pan_camera_to_spaceship(
     query: Query<&Transform, With<Spaceship>>,
     mut camera_query: Query<&mut Transform, (With<Camera>, Without<Spaceship>)>,
     time: Res<Time>,
+    tuning: Res<Tuning>,
 ) {
     let Ok(spaceship_transform) = query.get_single() else {
         return;
@@ -57,7 +136,7 @@ fn pan_camera_to_spaceship(
 
     camera_transform.translation = camera_transform.translation.lerp(
         spaceship_transform.translation + Vec3::new(0.0, camera_transform.translation.y, 0.0),
-        CAMERA_LERP_SPEED * time.delta_seconds(),
+        tuning.camera.lerp_speed * time.delta_seconds(),
     );
 
     camera_transform.look_at(spaceship_transform.translation, Vec3::Z);
@@ -67,12 +146,14 @@ fn zoom_camera_controls(
     mut scroll_evr: EventReader<MouseWheel>,
     mut camera_query: Query<&mut Transform, With<MainCamera>>,
     time: Res<Time>,
+    tuning: Res<Tuning>,
 ) {
     for event in scroll_evr.read() {
         for mut camera_transform in camera_query.iter_mut() {
             camera_transform.translation = camera_transform.translation.lerp(
-                camera_transform.translation + Vec3::new(0.0, event.y * CAMERA_SCROLL_FACTOR, 0.0),
-                CAMERA_LERP_SPEED * time.delta_seconds(),
+                camera_transform.translation
+                    + Vec3::new(0.0, event.y * tuning.camera.scroll_factor, 0.0),
+                tuning.camera.lerp_speed * time.delta_seconds(),
             );
         }
     }
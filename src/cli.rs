@@ -0,0 +1,4 @@
+// Returns the value following `flag` on the command line, e.g. `--seed 42`
+pub fn arg_value(flag: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != flag).nth(1)
+}
@@ -1,55 +1,210 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_rapier3d::prelude::*;
 
-use crate::{health::Health, schedule::InGameSet};
+use crate::{
+    collision_layers::{CollisionLayer, LayerTable},
+    damage::{DamageEvent, DamageKind, DamageSet},
+    health::Health,
+    schedule::InGameSet,
+    weapons::Projectile,
+};
 
+// Relative speed at which a hit deals exactly `CollisionDamage::amount`
+const IMPACT_REFERENCE_SPEED: f32 = 10.0;
+
+// Damage dealt to whatever this runs into
 #[derive(Component, Debug)]
 pub struct CollisionDamage {
     pub amount: f32,
+    pub kind: DamageKind,
+    // Scale `amount` linearly by the speed of the impact. Off for weapons, whose damage is per hit.
+    pub scales_with_impact: bool,
 }
 
 impl CollisionDamage {
-    pub fn new(amount: f32) -> Self {
-        Self { amount }
+    pub fn new(amount: f32, kind: DamageKind) -> Self {
+        Self {
+            amount,
+            kind,
+            scales_with_impact: true,
+        }
+    }
+
+    pub fn flat(amount: f32, kind: DamageKind) -> Self {
+        Self {
+            amount,
+            kind,
+            scales_with_impact: false,
+        }
     }
 }
 
+// Velocities from just before the last physics step. Rapier has already resolved a contact by
+// the time it reports it, so the bodies' current velocities say little about how hard they hit.
+#[derive(Resource, Debug, Default)]
+struct ImpactVelocities(HashMap<Entity, Vec3>);
+
 pub struct CollisionDetectionPlugin;
 
 impl Plugin for CollisionDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, display_events.in_set(InGameSet::CollisionDetection))
-            .add_systems(
-                Update,
-                (display_events, apply_collision_damage)
-                    .chain()
-                    .in_set(InGameSet::EntityUpdates),
-            )
-            .add_event::<CollisionEvent>();
+        app.init_resource::<ImpactVelocities>().add_systems(
+            FixedUpdate,
+            (send_collision_damage, record_impact_velocities)
+                .chain()
+                .before(DamageSet)
+                .in_set(InGameSet::CollisionDetection),
+        );
     }
 }
 
-fn display_events(
+#[allow(clippy::type_complexity)]
+fn send_collision_damage(
     mut collision_events: EventReader<CollisionEvent>,
-    mut contact_force_events: EventReader<ContactForceEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    impact_velocities: Res<ImpactVelocities>,
+    query: Query<(
+        Option<&CollisionDamage>,
+        Option<&Velocity>,
+        Has<Health>,
+        Option<&CollisionLayer>,
+    )>,
+    projectile_query: Query<&Projectile>,
+    layer_table: Res<LayerTable>,
 ) {
     for collision_event in collision_events.read() {
-        println!("Collision detected: {:?}", collision_event);
-    }
-    for contact_force_event in contact_force_events.read() {
-        println!("Contact force detected: {:?}", contact_force_event);
+        let CollisionEvent::Started(first, second, _) = *collision_event else {
+            continue;
+        };
+        if same_owner(&projectile_query, first, second) {
+            continue;
+        }
+        let (Ok(first_item), Ok(second_item)) = (query.get(first), query.get(second)) else {
+            continue;
+        };
+        let velocity_of = |entity: Entity, velocity: Option<&Velocity>| {
+            impact_velocities
+                .0
+                .get(&entity)
+                .copied()
+                .or(velocity.map(|velocity| velocity.linvel))
+                .unwrap_or_default()
+        };
+        let impact_speed =
+            (velocity_of(first, first_item.1) - velocity_of(second, second_item.1)).length();
+
+        for ((source, (damage, _, _, source_layer)), (target, (_, _, has_health, target_layer))) in [
+            ((first, first_item), (second, second_item)),
+            ((second, second_item), (first, first_item)),
+        ] {
+            let Some(damage) = damage else {
+                continue;
+            };
+            if !has_health {
+                continue;
+            }
+            if let (Some(&source_layer), Some(&target_layer)) = (source_layer, target_layer) {
+                if !layer_table.deals_damage(source_layer, target_layer) {
+                    continue;
+                }
+            }
+            damage_events.send(DamageEvent {
+                source,
+                target,
+                amount: if damage.scales_with_impact {
+                    damage.amount * impact_speed / IMPACT_REFERENCE_SPEED
+                } else {
+                    damage.amount
+                },
+                kind: damage.kind,
+            });
+        }
     }
 }
 
-fn apply_collision_damage(
-    rapier_context: Res<RapierContext>,
-    mut query: Query<(&mut Health, Entity, &CollisionDamage)>,
+// Whether either entity was fired by the other, or both were fired by the same shooter
+fn same_owner(projectile_query: &Query<&Projectile>, first: Entity, second: Entity) -> bool {
+    let owner = |entity: Entity| {
+        projectile_query
+            .get(entity)
+            .map_or(entity, |projectile| projectile.owner)
+    };
+    owner(first) == owner(second)
+}
+
+fn record_impact_velocities(
+    mut impact_velocities: ResMut<ImpactVelocities>,
+    query: Query<(Entity, &Velocity)>,
 ) {
-    for (mut health, entity, collision_damage) in query.iter_mut() {
-        for contact_pair_group in rapier_context.contact_pairs_with(entity) {
-            if contact_pair_group.has_any_active_contacts() {
-                health.value -= collision_damage.amount;
-            }
+    impact_velocities.0.clear();
+    for (entity, velocity) in query.iter() {
+        impact_velocities.0.insert(entity, velocity.linvel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy_rapier3d::rapier::geometry::CollisionEventFlags;
+
+    use super::*;
+
+    fn collision_app() -> App {
+        let mut app = App::new();
+        app.add_event::<CollisionEvent>()
+            .add_event::<DamageEvent>()
+            .init_resource::<ImpactVelocities>()
+            .init_resource::<LayerTable>()
+            .add_systems(Update, send_collision_damage);
+        app
+    }
+
+    fn projectile(owner: Entity) -> Projectile {
+        Projectile {
+            owner,
+            lifetime: Timer::new(Duration::from_secs(1), TimerMode::Once),
+            max_range: 100.0,
+            origin: Vec3::ZERO,
         }
     }
+
+    // Targets of every `DamageEvent` sent for a collision between `first` and `second`
+    fn damaged_by_collision(app: &mut App, first: Entity, second: Entity) -> Vec<Entity> {
+        app.world.send_event(CollisionEvent::Started(
+            first,
+            second,
+            CollisionEventFlags::empty(),
+        ));
+        app.update();
+        app.world
+            .resource_mut::<Events<DamageEvent>>()
+            .drain()
+            .map(|event| event.target)
+            .collect()
+    }
+
+    #[test]
+    fn projectiles_never_hurt_their_owner_or_each_other() {
+        let mut app = collision_app();
+        let damage = || CollisionDamage::flat(10.0, DamageKind::Kinetic);
+        let shooter = app.world.spawn((Health::new(100.0), damage())).id();
+        let first = app
+            .world
+            .spawn((Health::new(1.0), damage(), projectile(shooter)))
+            .id();
+        let second = app
+            .world
+            .spawn((Health::new(1.0), damage(), projectile(shooter)))
+            .id();
+        let bystander = app.world.spawn(Health::new(100.0)).id();
+
+        assert!(damaged_by_collision(&mut app, first, shooter).is_empty());
+        assert!(damaged_by_collision(&mut app, shooter, second).is_empty());
+        assert!(damaged_by_collision(&mut app, first, second).is_empty());
+        assert_eq!(
+            damaged_by_collision(&mut app, first, bystander),
+            vec![bystander]
+        );
+    }
 }
@@ -0,0 +1,257 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::asset_loader::{parse_embedded, RonResource};
+
+// Which side of the fight a body is on. Each layer gets its own Rapier group bit.
+#[derive(Component, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionLayer {
+    Player,
+    PlayerProjectile,
+    Enemy,
+    EnemyProjectile,
+    // Asteroids and other debris
+    Environment,
+    Planet,
+}
+
+impl CollisionLayer {
+    pub const ALL: [Self; 6] = [
+        Self::Player,
+        Self::PlayerProjectile,
+        Self::Enemy,
+        Self::EnemyProjectile,
+        Self::Environment,
+        Self::Planet,
+    ];
+
+    fn group(self) -> Group {
+        Group::from_bits_truncate(1 << self as u32)
+    }
+
+    // The layer of whatever a body on this layer fires
+    pub fn projectile(self) -> Self {
+        match self {
+            Self::Enemy | Self::EnemyProjectile => Self::EnemyProjectile,
+            _ => Self::PlayerProjectile,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contact {
+    // Collide and push each other apart
+    Solid,
+    // Report the contact, so damage is still dealt, but pass through each other
+    Overlap,
+}
+
+// Everything a spawn function needs to put a body on a layer
+pub type LayerBundle = (CollisionLayer, CollisionGroups, SolverGroups);
+
+// Which layers touch and which hurt each other, from `assets/config/layers.ron`
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct LayerTable {
+    // Any pair not listed here passes straight through each other
+    pub interactions: Vec<(CollisionLayer, CollisionLayer, Contact)>,
+    // The layers each layer damages on contact
+    pub damage: HashMap<CollisionLayer, Vec<CollisionLayer>>,
+}
+
+impl Default for LayerTable {
+    fn default() -> Self {
+        parse_embedded(include_str!("../assets/config/layers.ron"))
+    }
+}
+
+impl RonResource for LayerTable {
+    const PATH: &'static str = "config/layers.ron";
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        for (i, &(a, b, _)) in self.interactions.iter().enumerate() {
+            if self.interactions[..i]
+                .iter()
+                .any(|&(c, d, _)| (a, b) == (c, d) || (a, b) == (d, c))
+            {
+                problems.push(format!("{:?} and {:?} are listed more than once", a, b));
+            }
+        }
+        for (&source, targets) in self.damage.iter() {
+            for &target in targets {
+                if self.contact(source, target).is_none() {
+                    problems.push(format!(
+                        "{:?} damages {:?}, but they never touch",
+                        source, target
+                    ));
+                }
+            }
+        }
+        problems
+    }
+}
+
+impl LayerTable {
+    pub fn contact(&self, a: CollisionLayer, b: CollisionLayer) -> Option<Contact> {
+        self.interactions
+            .iter()
+            .find(|&&(c, d, _)| (a, b) == (c, d) || (a, b) == (d, c))
+            .map(|&(_, _, contact)| contact)
+    }
+
+    pub fn deals_damage(&self, source: CollisionLayer, target: CollisionLayer) -> bool {
+        self.damage
+            .get(&source)
+            .is_some_and(|targets| targets.contains(&target))
+    }
+
+    pub fn groups(&self, layer: CollisionLayer) -> (CollisionGroups, SolverGroups) {
+        let mut touches = Group::NONE;
+        let mut pushes = Group::NONE;
+        for other in CollisionLayer::ALL {
+            match self.contact(layer, other) {
+                Some(Contact::Solid) => {
+                    touches |= other.group();
+                    pushes |= other.group();
+                }
+                Some(Contact::Overlap) => touches |= other.group(),
+                None => {}
+            }
+        }
+        (
+            CollisionGroups::new(layer.group(), touches),
+            SolverGroups::new(layer.group(), pushes),
+        )
+    }
+
+    pub fn bundle(&self, layer: CollisionLayer) -> LayerBundle {
+        let (collision_groups, solver_groups) = self.groups(layer);
+        (layer, collision_groups, solver_groups)
+    }
+}
+
+pub struct CollisionLayersPlugin;
+
+impl Plugin for CollisionLayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            regroup_on_table_change.run_if(resource_changed::<LayerTable>),
+        );
+    }
+}
+
+// Bodies keep the groups they were spawned with, so an edited table has to be pushed out to them
+fn regroup_on_table_change(
+    mut query: Query<(&CollisionLayer, &mut CollisionGroups, &mut SolverGroups)>,
+    table: Res<LayerTable>,
+) {
+    for (&layer, mut collision_groups, mut solver_groups) in query.iter_mut() {
+        let (new_collision_groups, new_solver_groups) = table.groups(layer);
+        *collision_groups = new_collision_groups;
+        *solver_groups = new_solver_groups;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CollisionLayer::*;
+
+    // Whether Rapier would let bodies with these groups interact at all
+    fn interacts(a: (Group, Group), b: (Group, Group)) -> bool {
+        a.0.intersects(b.1) && b.0.intersects(a.1)
+    }
+
+    fn collision(table: &LayerTable, layer: CollisionLayer) -> (Group, Group) {
+        let (groups, _) = table.groups(layer);
+        (groups.memberships, groups.filters)
+    }
+
+    fn solver(table: &LayerTable, layer: CollisionLayer) -> (Group, Group) {
+        let (_, groups) = table.groups(layer);
+        (groups.memberships, groups.filters)
+    }
+
+    #[test]
+    fn shipped_table_is_valid() {
+        assert_eq!(LayerTable::default().validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn solid_pairs_collide_both_ways() {
+        let table = LayerTable::default();
+        assert_eq!(table.contact(Player, Enemy), Some(Contact::Solid));
+        for (a, b) in [(Player, Enemy), (Enemy, Player)] {
+            assert!(interacts(collision(&table, a), collision(&table, b)));
+            assert!(interacts(solver(&table, a), solver(&table, b)));
+            assert!(collision(&table, a).1.contains(b.group()));
+            assert!(solver(&table, a).1.contains(b.group()));
+        }
+    }
+
+    #[test]
+    fn overlap_pairs_report_without_solver_contact() {
+        let table = LayerTable::default();
+        assert_eq!(
+            table.contact(EnemyProjectile, Player),
+            Some(Contact::Overlap)
+        );
+        for (a, b) in [(Player, EnemyProjectile), (EnemyProjectile, Player)] {
+            assert!(interacts(collision(&table, a), collision(&table, b)));
+            assert!(!interacts(solver(&table, a), solver(&table, b)));
+        }
+    }
+
+    #[test]
+    fn unlisted_pairs_pass_through() {
+        let table = LayerTable::default();
+        assert_eq!(table.contact(PlayerProjectile, EnemyProjectile), None);
+        assert!(!interacts(
+            collision(&table, PlayerProjectile),
+            collision(&table, EnemyProjectile)
+        ));
+    }
+
+    #[test]
+    fn damage_follows_the_table() {
+        let table = LayerTable::default();
+        for source in CollisionLayer::ALL {
+            for target in CollisionLayer::ALL {
+                let listed = table
+                    .damage
+                    .get(&source)
+                    .is_some_and(|targets| targets.contains(&target));
+                assert_eq!(table.deals_damage(source, target), listed);
+            }
+        }
+        assert!(table.deals_damage(EnemyProjectile, Player));
+        assert!(!table.deals_damage(Player, PlayerProjectile));
+        assert!(!table.deals_damage(EnemyProjectile, Enemy));
+    }
+
+    #[test]
+    fn rejects_pairs_listed_both_ways() {
+        let mut table = LayerTable::default();
+        table.interactions.push((Enemy, Player, Contact::Overlap));
+        assert_eq!(table.validate().len(), 1);
+    }
+
+    #[test]
+    fn rejects_damage_between_layers_that_never_touch() {
+        let mut table = LayerTable::default();
+        table
+            .damage
+            .entry(PlayerProjectile)
+            .or_default()
+            .push(EnemyProjectile);
+        assert_eq!(table.validate().len(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_layers() {
+        let text = "(interactions: [(Player, Ghost, Solid)], damage: {})";
+        assert!(ron::from_str::<LayerTable>(text).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    archetype::ArchetypeKind, health::Health, schedule::InGameSet, shield::SpaceShipShield,
+    weapons::Projectile,
+};
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageKind {
+    #[default]
+    Kinetic,
+    Explosive,
+    // Crashing into something massive enough to have its own pull, i.e. a planet
+    Gravitational,
+}
+
+// Every hit goes through one of these, so there is a single place where `Health` goes down
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct DamageEvent {
+    pub source: Entity,
+    pub target: Entity,
+    pub amount: f32,
+    pub kind: DamageKind,
+}
+
+// Who hit an entity most recently. Projectiles are credited to whoever fired them.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LastHit {
+    pub attacker: ArchetypeKind,
+    pub damage_kind: DamageKind,
+}
+
+// Fraction of each kind of damage that is shrugged off, from 0 (none) to 1 (immune)
+#[derive(Component, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Resistances {
+    pub kinetic: f32,
+    pub explosive: f32,
+    pub gravitational: f32,
+}
+
+impl Resistances {
+    pub fn against(&self, kind: DamageKind) -> f32 {
+        let resistance = match kind {
+            DamageKind::Kinetic => self.kinetic,
+            DamageKind::Explosive => self.explosive,
+            DamageKind::Gravitational => self.gravitational,
+        };
+        resistance.clamp(0.0, 1.0)
+    }
+
+    // What is left of `amount` once this resistance has been applied
+    pub fn filter(&self, amount: f32, kind: DamageKind) -> f32 {
+        amount * (1.0 - self.against(kind))
+    }
+}
+
+// Anything that sends `DamageEvent`s should run before this set
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub struct DamageSet;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .configure_sets(FixedUpdate, DamageSet.in_set(InGameSet::CollisionDetection))
+            .add_systems(FixedUpdate, apply_damage.in_set(DamageSet));
+    }
+}
+
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut query: Query<(&mut Health, Option<&Resistances>, Option<&SpaceShipShield>)>,
+    attacker_query: Query<(Option<&ArchetypeKind>, Option<&Projectile>)>,
+) {
+    for event in damage_events.read() {
+        let Ok((mut health, resistances, shield)) = query.get_mut(event.target) else {
+            continue;
+        };
+        let mut amount = resistances.map_or(event.amount, |resistances| {
+            resistances.filter(event.amount, event.kind)
+        });
+        if let Some(shield) = shield {
+            amount *= 1.0 - shield.damage_reduction.clamp(0.0, 1.0);
+        }
+        health.value -= amount;
+        if let Some(attacker) = attacker_kind(event.source, &attacker_query) {
+            commands.entity(event.target).try_insert(LastHit {
+                attacker,
+                damage_kind: event.kind,
+            });
+        }
+        debug!(
+            "{:?} took {} {:?} damage from {:?} ({} left)",
+            event.target, amount, event.kind, event.source, health.value
+        );
+    }
+}
+
+// Falls back on the projectile itself once whoever fired it is gone
+fn attacker_kind(
+    source: Entity,
+    attacker_query: &Query<(Option<&ArchetypeKind>, Option<&Projectile>)>,
+) -> Option<ArchetypeKind> {
+    let (kind, projectile) = attacker_query.get(source).ok()?;
+    projectile
+        .and_then(|projectile| attacker_query.get(projectile.owner).ok())
+        .and_then(|(owner_kind, _)| owner_kind)
+        .or(kind)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_KINDS: [DamageKind; 3] = [
+        DamageKind::Kinetic,
+        DamageKind::Explosive,
+        DamageKind::Gravitational,
+    ];
+
+    fn damage_app() -> App {
+        let mut app = App::new();
+        app.add_event::<DamageEvent>()
+            .add_systems(Update, apply_damage);
+        app
+    }
+
+    // Health left after one hit of `amount` of each kind
+    fn health_after_hits(target: impl Bundle + Clone, amount: f32) -> Vec<f32> {
+        ALL_KINDS
+            .iter()
+            .map(|&kind| {
+                let mut app = damage_app();
+                let target = app.world.spawn((Health::new(100.0), target.clone())).id();
+                app.world.send_event(DamageEvent {
+                    source: target,
+                    target,
+                    amount,
+                    kind,
+                });
+                app.update();
+                app.world.get::<Health>(target).unwrap().value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resistances_scale_each_kind() {
+        let resistances = Resistances {
+            kinetic: 0.5,
+            explosive: 0.25,
+            gravitational: 1.0,
+        };
+        assert_eq!(resistances.filter(10.0, DamageKind::Kinetic), 5.0);
+        assert_eq!(resistances.filter(10.0, DamageKind::Explosive), 7.5);
+        assert_eq!(resistances.filter(10.0, DamageKind::Gravitational), 0.0);
+        assert_eq!(
+            health_after_hits(resistances, 10.0),
+            vec![95.0, 92.5, 100.0]
+        );
+    }
+
+    #[test]
+    fn resistances_are_clamped() {
+        let resistances = Resistances {
+            kinetic: -1.0,
+            explosive: 2.0,
+            gravitational: 0.0,
+        };
+        assert_eq!(resistances.filter(10.0, DamageKind::Kinetic), 10.0);
+        assert_eq!(resistances.filter(10.0, DamageKind::Explosive), 0.0);
+    }
+
+    #[test]
+    fn shield_reduction_is_clamped() {
+        let overcharged = SpaceShipShield {
+            damage_reduction: 1.5,
+        };
+        let inverted = SpaceShipShield {
+            damage_reduction: -0.5,
+        };
+        let half = SpaceShipShield {
+            damage_reduction: 0.5,
+        };
+        // Never heals, and never makes a hit worse
+        assert_eq!(health_after_hits(overcharged, 10.0), vec![100.0; 3]);
+        assert_eq!(health_after_hits(inverted, 10.0), vec![90.0; 3]);
+        assert_eq!(health_after_hits(half, 10.0), vec![95.0; 3]);
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
-use crate::schedule::InGameSet;
+use crate::physics::TransformInterpolationSet;
 
 #[derive(Component, Debug)]
 pub struct DebugEntity;
@@ -12,7 +12,7 @@ impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (draw_kinematics).chain().after(InGameSet::EntityUpdates),
+            (draw_kinematics).chain().after(TransformInterpolationSet),
         )
         .add_systems(Update, update_config);
     }
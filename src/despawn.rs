@@ -1,22 +1,80 @@
 use bevy::prelude::*;
 
-use crate::{health::Health, schedule::InGameSet, state::GameState};
+use crate::{
+    archetype::ArchetypeKind,
+    damage::LastHit,
+    health::Health,
+    schedule::InGameSet,
+    state::{RunReset, RunResetSet},
+    weapons::Projectile,
+};
+
+// Sent once for everything that dies, as it is despawned. Projectiles running out of range or
+// time don't count.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct Killed {
+    pub entity: Entity,
+    pub entity_kind: ArchetypeKind,
+    pub position: Vec3,
+    // `None` when nothing is known to have hit it
+    pub by: Option<LastHit>,
+}
 
 pub struct DespawnPlugin;
 
 impl Plugin for DespawnPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (despawn_dead_entities).in_set(InGameSet::DespawnEntities),
-        )
-        .add_systems(OnEnter(GameState::GameOver), despawn_all_entities);
+        app.add_event::<Killed>()
+            .add_systems(
+                FixedUpdate,
+                (despawn_dead_entities, despawn_expired_projectiles)
+                    .in_set(InGameSet::DespawnEntities),
+            )
+            .add_systems(RunReset, despawn_all_entities.in_set(RunResetSet::Despawn));
     }
 }
 
-fn despawn_dead_entities(mut commands: Commands, query: Query<(Entity, &Health)>) {
-    for (entity, health) in query.iter() {
-        if health.value <= 0.0 {
+#[allow(clippy::type_complexity)]
+fn despawn_dead_entities(
+    mut commands: Commands,
+    mut killed_events: EventWriter<Killed>,
+    query: Query<(
+        Entity,
+        &Health,
+        &GlobalTransform,
+        Option<&ArchetypeKind>,
+        Option<&LastHit>,
+    )>,
+) {
+    for (entity, health, transform, kind, last_hit) in query.iter() {
+        if health.value > 0.0 {
+            continue;
+        }
+        if let Some(&entity_kind) = kind {
+            killed_events.send(Killed {
+                entity,
+                entity_kind,
+                position: transform.translation(),
+                by: last_hit.copied(),
+            });
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn despawn_expired_projectiles(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Projectile, &Transform, Option<&Health>)>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, transform, health) in query.iter_mut() {
+        // Already being despawned by `despawn_dead_entities`
+        if health.is_some_and(|health| health.value <= 0.0) {
+            continue;
+        }
+        let expired = projectile.lifetime.tick(time.delta()).finished();
+        let out_of_range = transform.translation.distance(projectile.origin) > projectile.max_range;
+        if expired || out_of_range {
             commands.entity(entity).despawn_recursive();
         }
     }
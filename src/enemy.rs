@@ -1,87 +1,113 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use std::ops::Range;
+use rand::Rng;
+use serde::Deserialize;
 
 use crate::{
-    asset_loader::SceneAssets, asteroids::get_random_position_around,
-    behaviors::get_random_walk_type, collision_detection::CollisionDamage, debug::DebugEntity,
-    health::Health, schedule::InGameSet, spaceship::Spaceship,
+    archetype::{ArchetypeKind, EntityArchetype},
+    asset_loader::SceneAssets,
+    behaviors::{get_random_walk_type, BehaviorState, WalkState},
+    collision_detection::CollisionDamage,
+    collision_layers::{CollisionLayer, LayerTable},
+    debug::DebugEntity,
+    enemy_combat::EnemyCombat,
+    health::Health,
+    schedule::InGameSet,
+    spaceship::Spaceship,
+    weapons::{equip_weapon, WeaponRegistry},
 };
+
 #[derive(Component)]
 pub struct Enemy;
 
-#[derive(Resource, Debug)]
-pub struct EnemySpawnTimer {
-    pub timer: Timer,
+// Per-spawn tweaks on top of the enemy archetype, picked by the wave director
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct EnemyVariant {
+    pub health_scale: f32,
+    // Unarmed enemies leave out the archetype's `combat` and can only ram
+    pub armed: bool,
 }
 
-pub struct EnemyPlugin;
+impl Default for EnemyVariant {
+    fn default() -> Self {
+        Self {
+            health_scale: 1.0,
+            armed: true,
+        }
+    }
+}
 
-const NUM_ENEMIES: usize = 10;
-const ENEMY_MASS: f32 = 1.0;
-const ENEMY_SPAWN_RANGE: Range<f32> = 100.0..500.0;
-// const ENEMY_SPAWN_RANGE: Range<f32> = 10.0..50.0;
-const ENEMY_HEALTH: f32 = 80.0;
-const ENEMY_COLLISION_DAMAGE: f32 = 3.5;
-const SPAWN_TIME_SECONDS: f32 = 0.5;
-const ENEMY_SCALE: Vec3 = Vec3::splat(1.);
+pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(EnemySpawnTimer {
-            timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
-        })
-        .add_systems(
-            Update,
-            ((spawn_enemies, rotate_to_face_player).in_set(InGameSet::EntityUpdates),),
+        app.add_systems(
+            FixedUpdate,
+            rotate_to_face_player.in_set(InGameSet::EntityUpdates),
         );
     }
 }
 
-fn spawn_enemies(
-    mut commands: Commands,
-    enemy_query: Query<&Transform, With<Enemy>>,
-    player_query: Query<&Transform, With<Spaceship>>,
-    mut spawn_timer: ResMut<EnemySpawnTimer>,
-    time: Res<Time>,
-    scene_assets: Res<SceneAssets>,
-) {
-    spawn_timer.timer.tick(time.delta());
-    if !spawn_timer.timer.just_finished() {
-        return;
-    }
-    let num_enemies = enemy_query.iter().len();
-    if num_enemies >= NUM_ENEMIES {
-        return;
-    }
-    let enemy_spawn_count = (NUM_ENEMIES - num_enemies).min(NUM_ENEMIES);
-    info!("Spawning {} enemies", enemy_spawn_count);
-    let player_pos = player_query.single().translation;
-    (0..enemy_spawn_count).for_each(|_| {
-        let (x, z) = get_random_position_around(player_pos, ENEMY_SPAWN_RANGE);
-        let translation = Vec3::new(x, 0.0, z);
-        commands.spawn((
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    translation: Vec3,
+    variant: EnemyVariant,
+    archetype: &EntityArchetype,
+    scene_assets: &SceneAssets,
+    layer_table: &LayerTable,
+    weapon_registry: &WeaponRegistry,
+    rng: &mut impl Rng,
+) -> Entity {
+    let scale = archetype.scale.sample(rng);
+    let health = archetype.health * variant.health_scale;
+    let enemy = commands
+        .spawn((
             SceneBundle {
                 scene: scene_assets.aliens.clone(),
-                transform: Transform::from_translation(translation).with_scale(ENEMY_SCALE),
+                transform: Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
                 ..default()
             },
-            Collider::cuboid(
-                ENEMY_SCALE.x / 2.0,
-                ENEMY_SCALE.y / 2.0,
-                ENEMY_SCALE.z / 2.0,
-            ),
-            ColliderMassProperties::Density(ENEMY_MASS),
+            archetype.collider.collider(),
+            ColliderMassProperties::Density(archetype.density.sample(rng)),
+            ReadMassProperties::default(),
             Velocity::default(),
             Enemy,
-            Health::new(ENEMY_HEALTH),
-            CollisionDamage::new(ENEMY_COLLISION_DAMAGE),
+            ArchetypeKind::Enemy,
+            layer_table.bundle(CollisionLayer::Enemy),
+            Health::new(health),
+            CollisionDamage::new(archetype.collision_damage, archetype.damage_kind),
+            archetype.resistances,
+            ActiveEvents::COLLISION_EVENTS,
             ExternalForce::default(),
-            get_random_walk_type(),
             DebugEntity,
             RigidBody::Dynamic,
-        ));
-    });
+        ))
+        .id();
+    commands
+        .entity(enemy)
+        .insert(WalkState::new(&archetype.walk, rng));
+    match &archetype.behavior {
+        Some(graph) => {
+            commands
+                .entity(enemy)
+                .insert(BehaviorState::new(graph.clone(), health));
+        }
+        None => {
+            let walk_type = archetype
+                .walk_type
+                .unwrap_or_else(|| get_random_walk_type(rng));
+            commands.entity(enemy).insert(walk_type);
+        }
+    }
+    if let Some(combat) = archetype.combat.filter(|_| variant.armed) {
+        commands.entity(enemy).insert(EnemyCombat::new(combat));
+        if let Some(stats) = weapon_registry.get(combat.weapon) {
+            equip_weapon(commands, enemy, stats);
+        }
+    }
+    enemy
 }
 
 fn rotate_to_face_player(
@@ -0,0 +1,220 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    archetype::Archetypes,
+    asset_loader::SceneAssets,
+    behaviors::{Behavior, BehaviorState},
+    collision_layers::{CollisionLayer, LayerTable},
+    schedule::InGameSet,
+    spaceship::Spaceship,
+    steering::flatten,
+    weapons::{spawn_projectiles, turn_toward, Weapon, WeaponKind, WeaponRegistry},
+};
+
+// How an enemy fights, from the `combat` section of its archetype
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CombatStats {
+    pub weapon: WeaponKind,
+    // Distance the enemy tries to hold from the player while attacking
+    pub engage_distance: f32,
+    // Only fires while the player is at most this far away
+    pub fire_range: f32,
+    // Radians per second the enemy's aim can swing
+    pub turn_rate: f32,
+    // Fires once its aim is within this many degrees of where the player will be
+    pub aim_tolerance_degrees: f32,
+}
+
+#[derive(Component, Debug)]
+pub struct EnemyCombat {
+    pub stats: CombatStats,
+    // Where the enemy's gun points. Swings toward the target at `turn_rate`, so shots take
+    // time to line up.
+    pub aim: Vec3,
+}
+
+impl EnemyCombat {
+    pub fn new(stats: CombatStats) -> Self {
+        Self {
+            stats,
+            aim: Vec3::Z,
+        }
+    }
+}
+
+pub struct EnemyCombatPlugin;
+
+impl Plugin for EnemyCombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, fire_at_player.in_set(InGameSet::EntityUpdates));
+    }
+}
+
+// Direction to fire a projectile at `speed` so it meets a target moving at constant
+// `target_velocity`. `None` if the projectile can never catch up.
+pub fn lead_direction(
+    shooter: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    speed: f32,
+) -> Option<Vec3> {
+    // Solve |offset + target_velocity * t| = speed * t for the earliest t > 0
+    let offset = target - shooter;
+    let a = target_velocity.length_squared() - speed * speed;
+    let b = 2.0 * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let time = if a.abs() < f32::EPSILON {
+        (b < 0.0).then(|| -c / b)?
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+            .into_iter()
+            .filter(|time| *time > 0.0)
+            .min_by(f32::total_cmp)?
+    };
+    (offset + target_velocity * time).try_normalize()
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn fire_at_player(
+    mut commands: Commands,
+    mut enemy_query: Query<(
+        Entity,
+        &Transform,
+        &Children,
+        &CollisionLayer,
+        &mut EnemyCombat,
+        Option<&BehaviorState>,
+    )>,
+    mut weapon_query: Query<&mut Weapon>,
+    player_query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    registry: Res<WeaponRegistry>,
+    archetypes: Res<Archetypes>,
+    scene_assets: Res<SceneAssets>,
+    layer_table: Res<LayerTable>,
+    time: Res<Time>,
+) {
+    let Ok((player_transform, player_velocity)) = player_query.get_single() else {
+        return;
+    };
+    for (enemy, transform, children, layer, mut combat, state) in enemy_query.iter_mut() {
+        let Some(stats) = registry.get(combat.stats.weapon) else {
+            continue;
+        };
+        let Some(speed) = stats.projectile_speed() else {
+            continue;
+        };
+        let offset = flatten(player_transform.translation - transform.translation);
+        let Some(desired) =
+            lead_direction(Vec3::ZERO, offset, flatten(player_velocity.linvel), speed)
+                .or(offset.try_normalize())
+        else {
+            continue;
+        };
+        let max_turn = (combat.stats.turn_rate * time.delta_seconds()).min(PI);
+        combat.aim = turn_toward(combat.aim, desired, max_turn);
+
+        // Enemies driven by a behavior graph only shoot while attacking
+        if state.is_some_and(|state| state.current != Behavior::Attack) {
+            continue;
+        }
+        if offset.length() > combat.stats.fire_range {
+            continue;
+        }
+        if combat.aim.angle_between(desired) > combat.stats.aim_tolerance_degrees.to_radians() {
+            continue;
+        }
+        let Some(mut weapon) = children
+            .iter()
+            .copied()
+            .find(|&child| {
+                weapon_query
+                    .get(child)
+                    .is_ok_and(|w| w.kind == combat.stats.weapon)
+            })
+            .and_then(|child| weapon_query.get_mut(child).ok())
+        else {
+            continue;
+        };
+        if !weapon.ready() {
+            continue;
+        }
+        // Projectiles leave from the back of the shooter's transform, matching the ship model
+        let muzzle =
+            Transform::from_translation(transform.translation).looking_to(-combat.aim, Vec3::Y);
+        spawn_projectiles(
+            &mut commands,
+            enemy,
+            &muzzle,
+            stats,
+            &archetypes.missile,
+            &scene_assets,
+            layer_table.bundle(layer.projectile()),
+        );
+        weapon.use_round();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks that a projectile fired along `direction` reaches the target at the same moment
+    fn meets(direction: Vec3, target: Vec3, target_velocity: Vec3, speed: f32) -> bool {
+        // Where both are at `time`, measured along `direction`: speed * t = d·target + d·v * t
+        let time = direction.dot(target) / (speed - direction.dot(target_velocity));
+        let projectile = direction * speed * time;
+        time > 0.0 && projectile.distance(target + target_velocity * time) < 1e-2
+    }
+
+    #[test]
+    fn aims_straight_at_a_stationary_target() {
+        let target = Vec3::new(30.0, 0.0, 40.0);
+        let direction = lead_direction(Vec3::ZERO, target, Vec3::ZERO, 20.0).unwrap();
+        assert!(
+            direction.distance(target.normalize()) < 1e-5,
+            "{}",
+            direction
+        );
+    }
+
+    #[test]
+    fn leads_a_crossing_target() {
+        let (shooter, target, velocity) = (
+            Vec3::new(5.0, 0.0, 5.0),
+            Vec3::new(105.0, 0.0, 5.0),
+            Vec3::new(0.0, 0.0, 10.0),
+        );
+        let direction = lead_direction(shooter, target, velocity, 20.0).unwrap();
+        assert!(direction.z > 0.0, "{}", direction);
+        assert!(meets(direction, target - shooter, velocity, 20.0));
+    }
+
+    #[test]
+    fn gives_up_on_a_faster_target_running_away() {
+        let target = Vec3::new(100.0, 0.0, 0.0);
+        let velocity = Vec3::new(30.0, 0.0, 0.0);
+        assert_eq!(lead_direction(Vec3::ZERO, target, velocity, 20.0), None);
+    }
+
+    #[test]
+    fn handles_a_target_as_fast_as_the_projectile() {
+        let target = Vec3::new(100.0, 0.0, 0.0);
+        // Closing in at an angle can be caught
+        let closing = Vec3::new(-12.0, 0.0, 16.0);
+        let direction = lead_direction(Vec3::ZERO, target, closing, 20.0).unwrap();
+        assert!(meets(direction, target, closing, 20.0));
+        // Moving away or straight across never can
+        for velocity in [Vec3::new(20.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 20.0)] {
+            assert_eq!(lead_direction(Vec3::ZERO, target, velocity, 20.0), None);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+use crate::{
+    asteroids::AsteroidPlugin, behaviors::BehaviorsPlugin,
+    collision_detection::CollisionDetectionPlugin, collision_layers::CollisionLayersPlugin,
+    damage::DamagePlugin, despawn::DespawnPlugin, enemy::EnemyPlugin,
+    enemy_combat::EnemyCombatPlugin, gravity::GravityPlugin, physics::PhysicsPlugin,
+    planet::PlanetPlugin, player_input::PlayerInputPlugin, replay::ReplayPlugin, rng::RngPlugin,
+    schedule::SchedulePlugin, score::ScorePlugin, shield::ShieldPlugin, spaceship::SpaceshipPlugin,
+    spatial::SpatialPlugin, state::StatePlugin, waves::WaveDirectorPlugin, weapons::WeaponsPlugin,
+};
+
+// Every plugin that drives the simulation itself. None of these need a window, a GPU or
+// loaded assets, so the same group is shared by the game binary and the headless app.
+pub struct GamePlugins;
+
+impl PluginGroup for GamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(RngPlugin)
+            .add(SchedulePlugin)
+            .add(PhysicsPlugin)
+            .add(SpatialPlugin)
+            .add(PlayerInputPlugin)
+            // Needs `GameRng` and `InputSource` to exist already
+            .add(ReplayPlugin)
+            .add(StatePlugin)
+            .add(SpaceshipPlugin)
+            .add(ShieldPlugin)
+            .add(WeaponsPlugin)
+            .add(AsteroidPlugin)
+            .add(PlanetPlugin)
+            .add(GravityPlugin)
+            .add(ScorePlugin)
+            .add(EnemyPlugin)
+            // Reads `Score` for the difficulty curve
+            .add(WaveDirectorPlugin)
+            .add(EnemyCombatPlugin)
+            .add(BehaviorsPlugin)
+            .add(CollisionDetectionPlugin)
+            .add(CollisionLayersPlugin)
+            .add(DamagePlugin)
+            .add(DespawnPlugin)
+    }
+}
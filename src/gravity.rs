@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{planet::Planet, schedule::InGameSet, spatial::SpatialGrid};
+
+// Tuned for game-scale masses (planets weigh ~1e9), not the real constant
+const GRAVITATIONAL_CONSTANT: f32 = 1.5e-5;
+const SOFTENING_LENGTH: f32 = 10.0;
+const MAX_RANGE: f32 = 300.0;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct GravityConfig {
+    pub g: f32,
+    // Added to every distance so nothing is flung away when it passes through a planet's center
+    pub softening: f32,
+    // Planets further away than this exert no pull at all
+    pub max_range: f32,
+    pub planets_attract_planets: bool,
+}
+
+impl Default for GravityConfig {
+    fn default() -> Self {
+        Self {
+            g: GRAVITATIONAL_CONSTANT,
+            softening: SOFTENING_LENGTH,
+            max_range: MAX_RANGE,
+            // Planets fall into each other within seconds at this G, so the map stays still by default
+            planets_attract_planets: false,
+        }
+    }
+}
+
+impl GravityConfig {
+    // Pull of a `source_mass` at `source` on a `target_mass` at `target`, flattened onto the XZ plane
+    pub fn force_between(
+        &self,
+        source: Vec3,
+        source_mass: f32,
+        target: Vec3,
+        target_mass: f32,
+    ) -> Vec3 {
+        let offset = Vec3::new(source.x - target.x, 0.0, source.z - target.z);
+        let distance_squared = offset.length_squared();
+        if distance_squared > self.max_range * self.max_range {
+            return Vec3::ZERO;
+        }
+        // Plummer softening: G m1 m2 r / (r^2 + e^2)^(3/2)
+        let softened = distance_squared + self.softening * self.softening;
+        offset * (self.g * source_mass * target_mass / (softened * softened.sqrt()))
+    }
+}
+
+pub struct GravityPlugin;
+
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GravityConfig>().add_systems(
+            FixedUpdate,
+            attract_objects.in_set(InGameSet::EntityUpdates),
+        );
+    }
+}
+
+// Each planet only visits the bodies the spatial grid puts inside its `max_range`
+fn attract_objects(
+    config: Res<GravityConfig>,
+    grid: Res<SpatialGrid>,
+    planet_query: Query<(Entity, &Transform, &ReadMassProperties), With<Planet>>,
+    mut body_query: Query<(
+        &Transform,
+        &ReadMassProperties,
+        &mut ExternalForce,
+        Has<Planet>,
+    )>,
+) {
+    for (planet, planet_transform, planet_mass_props) in planet_query.iter() {
+        for (entity, _) in grid.within(planet_transform.translation, config.max_range) {
+            if entity == planet {
+                continue;
+            }
+            let Ok((transform, mass_props, mut ext_force, is_planet)) = body_query.get_mut(entity)
+            else {
+                continue;
+            };
+            if is_planet && !config.planets_attract_planets {
+                continue;
+            }
+            ext_force.force += config.force_between(
+                planet_transform.translation,
+                planet_mass_props.get().mass,
+                transform.translation,
+                mass_props.get().mass,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASS: f32 = 1e9;
+
+    #[test]
+    fn softening_keeps_zero_distance_finite() {
+        let config = GravityConfig::default();
+        let force = config.force_between(Vec3::ZERO, MASS, Vec3::ZERO, 1.0);
+        assert!(force.is_finite());
+        assert_eq!(force, Vec3::ZERO);
+
+        let close = config.force_between(Vec3::new(0.01, 0.0, 0.0), MASS, Vec3::ZERO, 1.0);
+        assert!(close.is_finite());
+        assert!(close.x > 0.0);
+    }
+
+    #[test]
+    fn forces_are_equal_and_opposite() {
+        let config = GravityConfig::default();
+        let a = Vec3::new(10.0, 0.0, -30.0);
+        let b = Vec3::new(-40.0, 0.0, 25.0);
+        let on_b = config.force_between(a, MASS, b, 2.0);
+        let on_a = config.force_between(b, 2.0, a, MASS);
+        assert!(on_b.length() > 0.0);
+        assert_eq!(on_b, -on_a);
+        // Pulls `b` towards `a`
+        assert!(on_b.dot(a - b) > 0.0);
+    }
+
+    #[test]
+    fn no_pull_beyond_max_range() {
+        let config = GravityConfig::default();
+        let inside = Vec3::new(config.max_range - 1.0, 0.0, 0.0);
+        let outside = Vec3::new(config.max_range + 1.0, 0.0, 0.0);
+        assert_ne!(
+            config.force_between(Vec3::ZERO, MASS, inside, 1.0),
+            Vec3::ZERO
+        );
+        assert_eq!(
+            config.force_between(Vec3::ZERO, MASS, outside, 1.0),
+            Vec3::ZERO
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    archetype::Archetypes,
+    asset_loader::SceneAssets,
+    collision_layers::LayerTable,
+    game::GamePlugins,
+    physics::{rapier_configuration, FIXED_TIMESTEP},
+    replay::{start_playback, Replay},
+    rng::GameRng,
+    score::ScoreTable,
+    settings::Settings,
+    sound::{AudioBackend, AudioTable, SoundPlugin},
+    state::GameState,
+    tuning::Tuning,
+    waves::WaveTable,
+    weapons::WeaponRegistry,
+};
+
+pub const HEADLESS_SEED: u64 = 0;
+
+// Builds an app that runs the gameplay plugins without a window, renderer or loaded models.
+// Sound is mixed as usual but only logged, see `NullAudioLog`.
+// Time advances by exactly one `FIXED_TIMESTEP` per `update()`, so a run is the same on every machine.
+// The app is already queued to enter `GameState::InGame` on its first update.
+pub fn headless_app() -> App {
+    headless_app_with_seed(HEADLESS_SEED)
+}
+
+pub fn headless_app_with_seed(seed: u64) -> App {
+    let mut app = App::new();
+    app.insert_resource(GameRng::new(seed))
+        .add_plugins(MinimalPlugins)
+        .add_plugins((
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+        ))
+        // Rapier's async collider systems want this, even though nothing is ever loaded
+        .init_asset::<Mesh>()
+        // Default handles: the scenes never resolve, entities are spawned with colliders only
+        .init_resource::<SceneAssets>()
+        .init_resource::<Archetypes>()
+        .init_resource::<Tuning>()
+        .init_resource::<WeaponRegistry>()
+        .init_resource::<LayerTable>()
+        .init_resource::<WaveTable>()
+        .init_resource::<ScoreTable>()
+        .init_resource::<AudioTable>()
+        // Defaults, the user's settings file is never read
+        .init_resource::<Settings>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            FIXED_TIMESTEP,
+        )))
+        .insert_resource(rapier_configuration())
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+        // Nothing is loaded, so start past the splash screen and go straight from the loading
+        // gate into the game, which is when the world gets spawned
+        .insert_state(GameState::Loading)
+        .add_plugins(GamePlugins)
+        .add_plugins(SoundPlugin {
+            backend: AudioBackend::Null,
+        });
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::InGame);
+    app
+}
+
+// Reproduces a recorded session: same seed, and the recorded actions instead of the keyboard.
+pub fn headless_app_from_replay(replay: Replay) -> App {
+    let mut app = headless_app_with_seed(replay.seed);
+    start_playback(&mut app.world, replay);
+    app
+}
+
+// Advances a headless app by `frames` fixed steps
+pub fn step(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    score::Score,
+    state::GameState,
+    user_data::{load_user_data, save_user_data},
+    waves::WaveDirector,
+};
+
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HighScore {
+    pub points: u64,
+    pub kills: u32,
+    pub wave: u32,
+    pub survival_seconds: f32,
+    pub best_combo: u32,
+}
+
+// Best runs first, kept in the user's data directory
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    // Where the run that just ended placed, if it made the table
+    #[serde(skip)]
+    pub latest: Option<usize>,
+}
+
+impl HighScores {
+    // Returns where `entry` placed, or `None` if it didn't make the table
+    pub fn submit(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.points > other.points)
+            .unwrap_or(self.entries.len());
+        self.latest = (rank < MAX_HIGH_SCORES).then(|| {
+            self.entries.insert(rank, entry);
+            self.entries.truncate(MAX_HIGH_SCORES);
+            rank
+        });
+        self.latest
+    }
+}
+
+// Kept out of `GamePlugins`, so the headless app never touches the user's files
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_user_data::<HighScores>(HIGH_SCORES_FILE).unwrap_or_default())
+            .add_systems(OnEnter(GameState::GameOver), record_high_score);
+    }
+}
+
+fn record_high_score(
+    mut high_scores: ResMut<HighScores>,
+    score: Res<Score>,
+    director: Res<WaveDirector>,
+) {
+    let entry = HighScore {
+        points: score.points(),
+        kills: score.total_kills(),
+        wave: director.wave,
+        survival_seconds: score.survival_seconds,
+        best_combo: score.best_combo,
+    };
+    info!("Run over: {:?}", entry);
+    if let Some(rank) = high_scores.submit(entry) {
+        info!("New high score, #{}", rank + 1);
+        save_user_data(HIGH_SCORES_FILE, &*high_scores);
+    }
+}
+
+// One line per entry, for the menus and the game over screen
+pub fn spawn_high_score_table(
+    parent: &mut ChildBuilder,
+    high_scores: &HighScores,
+    text_style: TextStyle,
+    highlight: Color,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("High Scores", text_style.clone()));
+            if high_scores.entries.is_empty() {
+                parent.spawn(TextBundle::from_section("None yet", text_style.clone()));
+            }
+            for (i, entry) in high_scores.entries.iter().enumerate() {
+                let seconds = entry.survival_seconds as u32;
+                let line = format!(
+                    "{:>2}. {:>8}  wave {:>2}  {:>3} kills  {}:{:02}",
+                    i + 1,
+                    entry.points,
+                    entry.wave,
+                    entry.kills,
+                    seconds / 60,
+                    seconds % 60
+                );
+                let mut style = text_style.clone();
+                if high_scores.latest == Some(i) {
+                    style.color = highlight;
+                }
+                parent.spawn(TextBundle::from_section(line, style));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(points: u64) -> HighScore {
+        HighScore {
+            points,
+            kills: 0,
+            wave: 0,
+            survival_seconds: 0.0,
+            best_combo: 0,
+        }
+    }
+
+    fn points(high_scores: &HighScores) -> Vec<u64> {
+        high_scores
+            .entries
+            .iter()
+            .map(|entry| entry.points)
+            .collect()
+    }
+
+    #[test]
+    fn entries_are_kept_best_first() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.submit(entry(50)), Some(0));
+        assert_eq!(high_scores.submit(entry(100)), Some(0));
+        assert_eq!(high_scores.submit(entry(70)), Some(1));
+        assert_eq!(high_scores.submit(entry(10)), Some(3));
+        assert_eq!(points(&high_scores), vec![100, 70, 50, 10]);
+        assert_eq!(high_scores.latest, Some(3));
+    }
+
+    #[test]
+    fn ties_go_below_the_earlier_run() {
+        let mut high_scores = HighScores::default();
+        high_scores.submit(entry(100));
+        let mut tie = entry(100);
+        tie.kills = 1;
+        assert_eq!(high_scores.submit(tie), Some(1));
+        assert_eq!(high_scores.entries[1].kills, 1);
+    }
+
+    #[test]
+    fn table_is_truncated_and_misses_are_not_placed() {
+        let mut high_scores = HighScores::default();
+        for points in 1..=MAX_HIGH_SCORES as u64 {
+            high_scores.submit(entry(points * 10));
+        }
+        assert_eq!(high_scores.submit(entry(55)), Some(5));
+        assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.entries.last().unwrap().points, 20);
+
+        // Below everything, or tied with the last entry, on a full table
+        assert_eq!(high_scores.submit(entry(5)), None);
+        assert_eq!(high_scores.submit(entry(20)), None);
+        assert_eq!(high_scores.latest, None);
+        assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+    }
+}
@@ -0,0 +1,474 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    archetype::Archetypes,
+    asteroids::Asteroid,
+    enemy::Enemy,
+    health::Health,
+    menus::despawn_screen,
+    planet::Planet,
+    score::Score,
+    shield::Shield,
+    spaceship::Spaceship,
+    state::GameState,
+    waves::{WaveDirector, WaveEvent, WavePhase},
+    weapons::{SelectedWeapon, Weapon, WeaponRegistry},
+};
+
+const TEXT_COLOR: Color = Color::WHITE;
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+const BAR_BACKGROUND: Color = Color::rgb(0.15, 0.15, 0.15);
+const HEALTH_COLOR: Color = Color::rgb(0.8, 0.25, 0.25);
+const SHIELD_COLOR: Color = Color::rgb(0.3, 0.6, 1.0);
+const BAR_WIDTH: f32 = 200.0;
+const BANNER_SECONDS: f32 = 3.0;
+
+// Side of the minimap in pixels, and how far from the ship it reaches in world units
+const MINIMAP_SIZE: f32 = 180.0;
+const MINIMAP_RANGE: f32 = 600.0;
+const SHIP_DOT_COLOR: Color = Color::WHITE;
+const PLANET_DOT_COLOR: Color = Color::rgb(0.4, 0.8, 0.4);
+const ENEMY_DOT_COLOR: Color = Color::rgb(1.0, 0.3, 0.2);
+const ASTEROID_DOT_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+
+// Tag component used to tag everything on the HUD
+#[derive(Component)]
+struct OnHudScreen;
+
+#[derive(Component)]
+struct HealthBar;
+
+#[derive(Component)]
+struct ShieldBar;
+
+#[derive(Component)]
+struct WeaponText;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct WaveText;
+
+#[derive(Component)]
+struct TimeText;
+
+// Announces wave changes in the middle of the screen, then hides again
+#[derive(Component)]
+struct WaveBanner {
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct Minimap;
+
+// A blip on the minimap, following `target`
+#[derive(Component)]
+struct MinimapDot {
+    target: Entity,
+    // Far away asteroids are just clutter, but planets and enemies stay pinned to the edge
+    pin_to_edge: bool,
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        // Paused and back again re-enters `InGame`, so only build the HUD once
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            spawn_hud.run_if(not(any_with_component::<OnHudScreen>)),
+        )
+        .add_systems(OnEnter(GameState::Menu), despawn_screen::<OnHudScreen>)
+        .add_systems(
+            Update,
+            (
+                update_health_bar,
+                update_shield_bar,
+                update_weapon_text,
+                update_score_text,
+                update_wave_text,
+                update_time_text,
+                show_wave_banner,
+                (sync_minimap_dots, move_minimap_dots).chain(),
+            )
+                .run_if(any_with_component::<OnHudScreen>),
+        );
+    }
+}
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: TEXT_COLOR,
+        ..default()
+    }
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, label: &str, color: Color, marker: impl Component) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                margin: UiRect::bottom(Val::Px(6.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(label, text_style(18.0)).with_style(Style {
+                    width: Val::Px(60.0),
+                    ..default()
+                }),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(14.0),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: color.into(),
+                            ..default()
+                        },
+                        marker,
+                    ));
+                });
+        });
+}
+
+fn spawn_hud(mut commands: Commands) {
+    let panel_style = Style {
+        position_type: PositionType::Absolute,
+        flex_direction: FlexDirection::Column,
+        padding: UiRect::all(Val::Px(10.0)),
+        ..default()
+    };
+
+    // Ship status, top left
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..panel_style.clone()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            },
+            OnHudScreen,
+        ))
+        .with_children(|parent| {
+            spawn_bar(parent, "Hull", HEALTH_COLOR, HealthBar);
+            spawn_bar(parent, "Shield", SHIELD_COLOR, ShieldBar);
+            parent.spawn((TextBundle::from_section("", text_style(18.0)), WeaponText));
+        });
+
+    // Run progress, top right
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    align_items: AlignItems::FlexEnd,
+                    ..panel_style.clone()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            },
+            OnHudScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", text_style(28.0)), ScoreText));
+            parent.spawn((TextBundle::from_section("", text_style(18.0)), WaveText));
+            parent.spawn((TextBundle::from_section("", text_style(18.0)), TimeText));
+        });
+
+    // Wave announcements, top center
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(80.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnHudScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle {
+                    visibility: Visibility::Hidden,
+                    ..TextBundle::from_section("", text_style(40.0))
+                },
+                WaveBanner {
+                    timer: Timer::from_seconds(BANNER_SECONDS, TimerMode::Once),
+                },
+            ));
+        });
+
+    // Minimap, bottom right, with the ship in the middle
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    width: Val::Px(MINIMAP_SIZE),
+                    height: Val::Px(MINIMAP_SIZE),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            },
+            Minimap,
+            OnHudScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(dot_bundle(SHIP_DOT_COLOR, 6.0, Vec2::ZERO));
+        });
+}
+
+// A square blip centered `offset` pixels from the middle of the minimap
+fn dot_bundle(color: Color, size: f32, offset: Vec2) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Px(size),
+            height: Val::Px(size),
+            left: Val::Px(MINIMAP_SIZE / 2.0 + offset.x - size / 2.0),
+            top: Val::Px(MINIMAP_SIZE / 2.0 + offset.y - size / 2.0),
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    }
+}
+
+fn set_bar(style: &mut Style, fraction: f32) {
+    style.width = Val::Percent(fraction.clamp(0.0, 1.0) * 100.0);
+}
+
+fn update_health_bar(
+    ship_query: Query<&Health, (With<Spaceship>, Changed<Health>)>,
+    mut bar_query: Query<&mut Style, With<HealthBar>>,
+    archetypes: Res<Archetypes>,
+) {
+    let Ok(health) = ship_query.get_single() else {
+        return;
+    };
+    let max_health = archetypes.spaceship.health;
+    let fraction = if max_health > 0.0 {
+        health.value / max_health
+    } else {
+        0.0
+    };
+    for mut style in bar_query.iter_mut() {
+        set_bar(&mut style, fraction);
+    }
+}
+
+fn update_shield_bar(
+    ship_query: Query<&Shield, (With<Spaceship>, Changed<Shield>)>,
+    mut bar_query: Query<&mut Style, With<ShieldBar>>,
+) {
+    let Ok(shield) = ship_query.get_single() else {
+        return;
+    };
+    for mut style in bar_query.iter_mut() {
+        set_bar(&mut style, shield.fraction());
+    }
+}
+
+fn update_weapon_text(
+    ship_query: Query<(&SelectedWeapon, &Children), With<Spaceship>>,
+    weapon_query: Query<&Weapon>,
+    mut text_query: Query<&mut Text, With<WeaponText>>,
+    registry: Res<WeaponRegistry>,
+) {
+    let Ok((selected, children)) = ship_query.get_single() else {
+        return;
+    };
+    let Some(weapon) = children
+        .iter()
+        .filter_map(|&child| weapon_query.get(child).ok())
+        .find(|weapon| weapon.kind == selected.0)
+    else {
+        return;
+    };
+    let name = registry
+        .get(weapon.kind)
+        .map_or_else(|| format!("{:?}", weapon.kind), |stats| stats.name.clone());
+    let ammo = weapon
+        .ammo
+        .map_or_else(|| "-".to_string(), |ammo| ammo.to_string());
+    set_text(&mut text_query, format!("{}  {}", name, ammo));
+}
+
+fn update_score_text(score: Res<Score>, mut text_query: Query<&mut Text, With<ScoreText>>) {
+    let text = if score.multiplier > 1 {
+        format!("{}  x{}", score.points(), score.multiplier)
+    } else {
+        score.points().to_string()
+    };
+    set_text(&mut text_query, text);
+}
+
+fn update_wave_text(director: Res<WaveDirector>, mut text_query: Query<&mut Text, With<WaveText>>) {
+    let seconds = director.seconds_left().ceil();
+    let text = match director.phase {
+        WavePhase::Intermission => format!("Wave {} in {}", director.wave + 1, seconds),
+        WavePhase::Telegraph => format!("Wave {} incoming", director.wave),
+        WavePhase::Active => format!("Wave {}", director.wave),
+    };
+    set_text(&mut text_query, text);
+}
+
+fn update_time_text(score: Res<Score>, mut text_query: Query<&mut Text, With<TimeText>>) {
+    let seconds = score.survival_seconds as u32;
+    set_text(
+        &mut text_query,
+        format!("{}:{:02}", seconds / 60, seconds % 60),
+    );
+}
+
+// Only touches the text when it reads differently, so the layout isn't redone every frame
+fn set_text<T: Component>(text_query: &mut Query<&mut Text, With<T>>, value: String) {
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
+
+fn show_wave_banner(
+    mut wave_events: EventReader<WaveEvent>,
+    mut banner_query: Query<(&mut Text, &mut Visibility, &mut WaveBanner)>,
+    time: Res<Time>,
+) {
+    let Ok((mut text, mut visibility, mut banner)) = banner_query.get_single_mut() else {
+        return;
+    };
+    for event in wave_events.read() {
+        text.sections[0].value = match *event {
+            WaveEvent::Incoming { wave, enemies, .. } => {
+                format!("Wave {}: {} enemies incoming", wave, enemies)
+            }
+            WaveEvent::Started { wave, .. } => format!("Wave {}", wave),
+            WaveEvent::Ended {
+                wave,
+                cleared: true,
+            } => format!("Wave {} cleared", wave),
+            WaveEvent::Ended {
+                wave,
+                cleared: false,
+            } => format!("Wave {} is over", wave),
+        };
+        *visibility = Visibility::Inherited;
+        banner.timer.reset();
+    }
+    if banner.timer.tick(time.delta()).just_finished() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+// Adds a dot for everything that should be on the minimap and doesn't have one yet, and drops
+// the dots whose entity is gone
+#[allow(clippy::type_complexity)]
+fn sync_minimap_dots(
+    mut commands: Commands,
+    minimap_query: Query<Entity, With<Minimap>>,
+    dot_query: Query<(Entity, &MinimapDot)>,
+    tracked_query: Query<
+        (Entity, Has<Planet>, Has<Enemy>),
+        Or<(With<Planet>, With<Enemy>, With<Asteroid>)>,
+    >,
+) {
+    let Ok(minimap) = minimap_query.get_single() else {
+        return;
+    };
+    let mut has_dot = HashSet::new();
+    for (dot, MinimapDot { target, .. }) in dot_query.iter() {
+        if tracked_query.contains(*target) {
+            has_dot.insert(*target);
+        } else {
+            commands.entity(dot).despawn_recursive();
+        }
+    }
+    for (entity, is_planet, is_enemy) in tracked_query.iter() {
+        if has_dot.contains(&entity) {
+            continue;
+        }
+        let (color, size) = if is_planet {
+            (PLANET_DOT_COLOR, 10.0)
+        } else if is_enemy {
+            (ENEMY_DOT_COLOR, 5.0)
+        } else {
+            (ASTEROID_DOT_COLOR, 3.0)
+        };
+        let dot = commands
+            .spawn((
+                dot_bundle(color, size, Vec2::ZERO),
+                MinimapDot {
+                    target: entity,
+                    pin_to_edge: is_planet || is_enemy,
+                },
+            ))
+            .id();
+        commands.entity(minimap).add_child(dot);
+    }
+}
+
+fn move_minimap_dots(
+    mut dot_query: Query<(&MinimapDot, &mut Style, &mut Visibility)>,
+    transform_query: Query<&Transform, Without<Spaceship>>,
+    ship_query: Query<&Transform, With<Spaceship>>,
+) {
+    let Ok(ship_transform) = ship_query.get_single() else {
+        return;
+    };
+    let scale = MINIMAP_SIZE / 2.0 / MINIMAP_RANGE;
+    for (dot, mut style, mut visibility) in dot_query.iter_mut() {
+        let Ok(transform) = transform_query.get(dot.target) else {
+            continue;
+        };
+        // The camera looks straight down with +Z up, which puts +X on the left of the screen
+        let offset = transform.translation - ship_transform.translation;
+        let mut position = Vec2::new(-offset.x, -offset.z) * scale;
+        let in_range = position.abs().max_element() <= MINIMAP_SIZE / 2.0;
+        if !in_range && dot.pin_to_edge {
+            position *= MINIMAP_SIZE / 2.0 / position.abs().max_element();
+        }
+        if !in_range && !dot.pin_to_edge {
+            // Leaves the style alone, so the many far off asteroids don't redo the layout
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+        let Val::Px(size) = style.width else {
+            continue;
+        };
+        style.left = Val::Px(MINIMAP_SIZE / 2.0 + position.x - size / 2.0);
+        style.top = Val::Px(MINIMAP_SIZE / 2.0 + position.y - size / 2.0);
+    }
+}
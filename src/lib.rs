@@ -1,18 +1,44 @@
+pub mod archetype;
 pub mod asset_loader;
 pub mod asteroids;
 pub mod background;
 pub mod behaviors;
 pub mod camera;
+pub mod cli;
 pub mod collision_detection;
+pub mod collision_layers;
+pub mod damage;
 pub mod debug;
 pub mod despawn;
 pub mod enemy;
+pub mod enemy_combat;
+pub mod game;
+pub mod gravity;
+pub mod headless;
 pub mod health;
+pub mod high_scores;
+pub mod hud;
+pub mod loading;
 pub mod menus;
+pub mod physics;
 pub mod planet;
+pub mod player_input;
 pub mod post_processing;
+pub mod replay;
+pub mod rng;
 pub mod schedule;
+pub mod score;
+pub mod settings;
+pub mod shield;
+pub mod sound;
 pub mod spaceship;
+pub mod spatial;
 pub mod splash;
 pub mod stars;
 pub mod state;
+pub mod steering;
+pub mod synth;
+pub mod tuning;
+pub mod user_data;
+pub mod waves;
+pub mod weapons;
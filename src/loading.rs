@@ -0,0 +1,273 @@
+use bevy::{
+    app::AppExit,
+    asset::{LoadState, RecursiveDependencyLoadState},
+    prelude::*,
+};
+
+use crate::{
+    asset_loader::{ArchetypeAssets, SceneAssets},
+    menus::despawn_screen,
+    state::GameState,
+};
+
+const TEXT_COLOR: Color = Color::WHITE;
+const ERROR_COLOR: Color = Color::rgb(0.9, 0.3, 0.3);
+const BAR_BACKGROUND: Color = Color::rgb(0.15, 0.15, 0.15);
+const BAR_FILL: Color = Color::rgb(0.35, 0.75, 0.35);
+const BAR_WIDTH: f32 = 400.0;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Loading), loading_setup)
+            .add_systems(
+                Update,
+                (
+                    track_loading.run_if(not(resource_exists::<LoadingFailed>)),
+                    quit_on_escape.run_if(resource_exists::<LoadingFailed>),
+                )
+                    .run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(
+                OnExit(GameState::Loading),
+                despawn_screen::<OnLoadingScreen>,
+            );
+    }
+}
+
+// Tag component used to tag entities added on the loading screen
+#[derive(Component)]
+struct OnLoadingScreen;
+
+#[derive(Component)]
+struct ProgressBar;
+
+#[derive(Component)]
+struct ProgressText;
+
+// Present once anything fails, which keeps the game parked on the error screen
+#[derive(Resource)]
+struct LoadingFailed;
+
+enum Progress {
+    Pending,
+    Loaded,
+    Failed,
+}
+
+fn loading_setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            OnLoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Loading",
+                TextStyle {
+                    font_size: 40.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(20.0),
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR_FILL.into(),
+                            ..default()
+                        },
+                        ProgressBar,
+                    ));
+                });
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                ),
+                ProgressText,
+            ));
+        });
+}
+
+fn progress_of(asset_server: &AssetServer, handle: &UntypedHandle) -> Progress {
+    // A model that fails to parse only marks its file as failed, not the `#Scene0` inside it
+    let file_failed = handle.path().is_some_and(|path| {
+        asset_server
+            .get_path_ids(path.without_label())
+            .into_iter()
+            .any(|id| asset_server.get_load_state(id) == Some(LoadState::Failed))
+    });
+    match (
+        asset_server.get_load_state(handle.id()),
+        asset_server.get_recursive_dependency_load_state(handle.id()),
+    ) {
+        _ if file_failed => Progress::Failed,
+        (Some(LoadState::Failed), _) | (_, Some(RecursiveDependencyLoadState::Failed)) => {
+            Progress::Failed
+        }
+        (_, Some(RecursiveDependencyLoadState::Loaded)) => Progress::Loaded,
+        _ => Progress::Pending,
+    }
+}
+
+// Waits on every model and archetype file, since spawning before they resolve leaves
+// invisible entities with the wrong stats
+#[allow(clippy::too_many_arguments)]
+fn track_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scene_assets: Res<SceneAssets>,
+    archetype_assets: Res<ArchetypeAssets>,
+    mut bar_query: Query<&mut Style, With<ProgressBar>>,
+    mut text_query: Query<&mut Text, With<ProgressText>>,
+    screen_query: Query<Entity, With<OnLoadingScreen>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let handles: Vec<UntypedHandle> = [
+        &scene_assets.spaceship,
+        &scene_assets.missiles,
+        &scene_assets.asteroids,
+        &scene_assets.aliens,
+        &scene_assets.planets,
+    ]
+    .into_iter()
+    .map(|handle| handle.clone().untyped())
+    .chain(
+        archetype_assets
+            .handles
+            .values()
+            .map(|handle| handle.clone().untyped()),
+    )
+    .collect();
+
+    let mut loaded = 0;
+    let mut failed = vec![];
+    for handle in &handles {
+        match progress_of(&asset_server, handle) {
+            Progress::Loaded => loaded += 1,
+            Progress::Failed => failed.push(
+                handle
+                    .path()
+                    .map_or_else(|| format!("{:?}", handle.id()), ToString::to_string),
+            ),
+            Progress::Pending => {}
+        }
+    }
+
+    if !failed.is_empty() {
+        for path in &failed {
+            error!("Failed to load {}", path);
+        }
+        for entity in screen_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_error_screen(&mut commands, &failed);
+        commands.insert_resource(LoadingFailed);
+        return;
+    }
+
+    let fraction = loaded as f32 / handles.len() as f32;
+    for mut style in bar_query.iter_mut() {
+        style.width = Val::Percent(fraction * 100.0);
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("{} / {}", loaded, handles.len());
+    }
+    if loaded == handles.len() {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn spawn_error_screen(commands: &mut Commands, failed: &[String]) {
+    let text_style = TextStyle {
+        font_size: 20.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            OnLoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Failed to load game assets",
+                    TextStyle {
+                        font_size: 40.0,
+                        color: ERROR_COLOR,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+            for path in failed {
+                parent.spawn(TextBundle::from_section(path.clone(), text_style.clone()));
+            }
+            parent.spawn(
+                TextBundle::from_section(
+                    "Check that the assets folder sits next to the game, then restart. Press Esc to quit.",
+                    text_style.clone(),
+                )
+                .with_style(Style {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+        });
+}
+
+fn quit_on_escape(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_exit_events.send(AppExit);
+    }
+}
@@ -6,11 +6,10 @@ use bevy::{
 };
 use bevy_rapier3d::prelude::*;
 use jumpy::{
-    asset_loader::AssetLoaderPlugin, asteroids::AsteroidPlugin, behaviors::BehaviorsPlugin,
-    camera::CameraPlugin, collision_detection::CollisionDetectionPlugin, debug::DebugPlugin,
-    despawn::DespawnPlugin, enemy::EnemyPlugin, menus::MenusPlugin, planet::PlanetPlugin,
-    post_processing::PostProcessPlugin, schedule::SchedulePlugin, spaceship::SpaceshipPlugin,
-    splash::SplashPlugin, stars::StarsPlugin, state::StatePlugin,
+    asset_loader::AssetLoaderPlugin, camera::CameraPlugin, debug::DebugPlugin, game::GamePlugins,
+    high_scores::HighScoresPlugin, hud::HudPlugin, loading::LoadingPlugin, menus::MenusPlugin,
+    physics::rapier_configuration, post_processing::PostProcessPlugin, settings::SettingsPlugin,
+    sound::SoundPlugin, splash::SplashPlugin, stars::StarsPlugin,
 };
 use winit::window::Icon;
 
@@ -37,29 +36,23 @@ fn main() {
         }))
         .add_plugins(LogDiagnosticsPlugin::default())
         .add_plugins(FrameTimeDiagnosticsPlugin)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .insert_resource(rapier_configuration())
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
         .add_plugins(RapierDebugRenderPlugin::default())
-        .insert_resource(RapierConfiguration {
-            gravity: Vec3::ZERO,
-            ..default()
-        })
         // User configured plugins
+        .add_plugins(SettingsPlugin)
         .add_plugins(AssetLoaderPlugin)
-        .add_plugins(SpaceshipPlugin)
-        .add_plugins(AsteroidPlugin)
-        .add_plugins(PlanetPlugin)
+        .add_plugins(GamePlugins)
         .add_plugins(CameraPlugin)
-        .add_plugins(CollisionDetectionPlugin)
-        .add_plugins(DespawnPlugin)
-        .add_plugins(SchedulePlugin)
-        .add_plugins(StatePlugin)
+        .add_plugins(LoadingPlugin)
+        .add_plugins(HighScoresPlugin)
         .add_plugins(MenusPlugin)
+        .add_plugins(HudPlugin)
         .add_plugins(SplashPlugin)
         .add_plugins(DebugPlugin)
         .add_plugins(StarsPlugin)
-        .add_plugins(BehaviorsPlugin)
-        .add_plugins(EnemyPlugin)
         .add_plugins(PostProcessPlugin)
+        .add_plugins(SoundPlugin::default())
         .run();
 }
 
@@ -1,6 +1,14 @@
 use bevy::{app::AppExit, prelude::*};
 
-use crate::state::GameState;
+use crate::{
+    archetype::ArchetypeKind,
+    damage::LastHit,
+    high_scores::{spawn_high_score_table, HighScores},
+    score::Score,
+    settings::{DisplayQuality, Settings, Volume, MAX_VOLUME},
+    state::{GameState, ResetRun, RunSeed},
+    waves::WaveDirector,
+};
 
 const BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.9);
 const TEXT_COLOR: Color = Color::WHITE;
@@ -9,17 +17,31 @@ const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-enum DisplayQuality {
-    Low,
-    Medium,
-    High,
+// A value picked from one of the settings screens, stored in its own field of `Settings`
+trait SettingValue: Component + PartialEq + Copy {
+    fn get(settings: &Settings) -> Self;
+    fn set(self, settings: &mut Settings);
 }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-struct Volume(u32);
+impl SettingValue for DisplayQuality {
+    fn get(settings: &Settings) -> Self {
+        settings.display_quality
+    }
+
+    fn set(self, settings: &mut Settings) {
+        settings.display_quality = self;
+    }
+}
+
+impl SettingValue for Volume {
+    fn get(settings: &Settings) -> Self {
+        settings.volume
+    }
+
+    fn set(self, settings: &mut Settings) {
+        settings.volume = self;
+    }
+}
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 enum MenuState {
@@ -27,6 +49,9 @@ enum MenuState {
     Settings,
     SettingsDisplay,
     SettingsSound,
+    // Over the paused game
+    Pause,
+    GameOver,
     #[default]
     Disabled,
 }
@@ -35,6 +60,14 @@ enum MenuState {
 #[derive(Component)]
 struct OnMainMenuScreen;
 
+// Tag component used to tag entities added on the pause menu screen
+#[derive(Component)]
+struct OnPauseMenuScreen;
+
+// Tag component used to tag entities added on the game over screen
+#[derive(Component)]
+struct OnGameOverScreen;
+
 // Tag component used to tag entities added on the settings menu screen
 #[derive(Component)]
 struct OnSettingsMenuScreen;
@@ -60,19 +93,37 @@ enum MenuButtonAction {
     Settings,
     SettingsDisplay,
     SettingsSound,
-    BackToMainMenu,
+    // Back to whichever menu the settings were opened from
+    BackFromSettings,
     BackToSettings,
+    Resume,
+    // Starts over on the same seed
+    Retry,
+    NewRun,
+    QuitToMainMenu,
     Quit,
 }
 
 impl Plugin for MenusPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<MenuState>()
-            .insert_resource(DisplayQuality::Medium)
-            .insert_resource(Volume(5))
             .add_systems(OnEnter(GameState::Menu), menu_setup)
+            .add_systems(OnEnter(GameState::Paused), pause_menu_enter)
+            .add_systems(OnExit(GameState::Paused), menu_disable)
+            .add_systems(OnEnter(GameState::GameOver), game_over_menu_enter)
+            .add_systems(OnExit(GameState::GameOver), menu_disable)
             .add_systems(OnEnter(MenuState::Main), main_menu_setup)
             .add_systems(OnExit(MenuState::Main), despawn_screen::<OnMainMenuScreen>)
+            .add_systems(OnEnter(MenuState::Pause), pause_menu_setup)
+            .add_systems(
+                OnExit(MenuState::Pause),
+                despawn_screen::<OnPauseMenuScreen>,
+            )
+            .add_systems(OnEnter(MenuState::GameOver), game_over_setup)
+            .add_systems(
+                OnExit(MenuState::GameOver),
+                despawn_screen::<OnGameOverScreen>,
+            )
             .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
             .add_systems(
                 OnExit(MenuState::Settings),
@@ -101,7 +152,11 @@ impl Plugin for MenusPlugin {
             )
             .add_systems(
                 Update,
-                (menu_action, button_system).run_if(in_state(GameState::Menu)),
+                (menu_action, button_system).run_if(
+                    in_state(GameState::Menu)
+                        .or_else(in_state(GameState::Paused))
+                        .or_else(in_state(GameState::GameOver)),
+                ),
             );
     }
 }
@@ -127,24 +182,30 @@ fn button_system(
 // This system updates the settings when a new value for a setting is selected, and marks
 // the button as the one currently selected
 #[allow(clippy::type_complexity)]
-fn setting_button<T: Resource + Component + PartialEq + Copy>(
+fn setting_button<T: SettingValue>(
     interaction_query: Query<(&Interaction, &T, Entity), (Changed<Interaction>, With<Button>)>,
     mut selected_query: Query<(Entity, &mut BackgroundColor), With<SelectedOption>>,
     mut commands: Commands,
-    mut setting: ResMut<T>,
+    mut settings: ResMut<Settings>,
 ) {
     for (interaction, button_setting, entity) in &interaction_query {
-        if *interaction == Interaction::Pressed && *setting != *button_setting {
-            let (previous_button, mut previous_color) = selected_query.single_mut();
-            *previous_color = NORMAL_BUTTON.into();
-            commands.entity(previous_button).remove::<SelectedOption>();
+        if *interaction == Interaction::Pressed && T::get(&settings) != *button_setting {
+            // Nothing is selected when the saved value has no button
+            if let Ok((previous_button, mut previous_color)) = selected_query.get_single_mut() {
+                *previous_color = NORMAL_BUTTON.into();
+                commands.entity(previous_button).remove::<SelectedOption>();
+            }
             commands.entity(entity).insert(SelectedOption);
-            *setting = *button_setting;
+            button_setting.set(&mut settings);
         }
     }
 }
 
-fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn main_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+) {
     // Common style for all buttons on the screen
     let button_style = Style {
         width: Val::Px(250.0),
@@ -275,6 +336,17 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             parent.spawn(TextBundle::from_section("Quit", button_text_style));
                         });
                 });
+            // Next to the buttons
+            spawn_high_score_table(
+                parent,
+                &high_scores,
+                TextStyle {
+                    font_size: 24.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+                PRESSED_BUTTON,
+            );
         });
 }
 
@@ -282,6 +354,229 @@ fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
     menu_state.set(MenuState::Main);
 }
 
+fn pause_menu_enter(mut menu_state: ResMut<NextState<MenuState>>) {
+    menu_state.set(MenuState::Pause);
+}
+
+fn menu_disable(mut menu_state: ResMut<NextState<MenuState>>) {
+    menu_state.set(MenuState::Disabled);
+}
+
+fn pause_menu_setup(mut commands: Commands) {
+    let button_style = Style {
+        width: Val::Px(400.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            OnPauseMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "Paused",
+                            TextStyle {
+                                font_size: 80.0,
+                                color: TEXT_COLOR,
+                                ..default()
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(50.0)),
+                            ..default()
+                        }),
+                    );
+                    for (action, text) in [
+                        (MenuButtonAction::Resume, "Resume"),
+                        (MenuButtonAction::Settings, "Settings"),
+                        (MenuButtonAction::Retry, "Restart"),
+                        (MenuButtonAction::QuitToMainMenu, "Quit to Main Menu"),
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    text,
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+fn game_over_menu_enter(mut menu_state: ResMut<NextState<MenuState>>) {
+    menu_state.set(MenuState::GameOver);
+}
+
+fn cause_of_death(death: Option<LastHit>) -> &'static str {
+    match death.map(|hit| hit.attacker) {
+        Some(ArchetypeKind::Planet) => "Crashed into a planet",
+        Some(ArchetypeKind::Asteroid) => "Smashed by an asteroid",
+        Some(ArchetypeKind::Enemy) => "Destroyed by an enemy",
+        Some(ArchetypeKind::Spaceship) => "Caught in your own fire",
+        Some(ArchetypeKind::Missile) => "Hit by a stray missile",
+        None => "Destroyed",
+    }
+}
+
+fn game_over_setup(
+    mut commands: Commands,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    director: Res<WaveDirector>,
+) {
+    let button_style = Style {
+        width: Val::Px(250.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(15.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let summary_text_style = TextStyle {
+        font_size: 24.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    let seconds = score.survival_seconds as u32;
+    let kills = ArchetypeKind::ALL
+        .iter()
+        .filter_map(|kind| {
+            let count = score.kills.get(kind).copied().unwrap_or(0);
+            (count > 0).then(|| format!("{} {:?}", count, kind))
+        })
+        .collect::<Vec<_>>();
+    let summary = [
+        cause_of_death(score.death.and_then(|death| death.by)).to_string(),
+        format!("Score: {}", score.points()),
+        format!(
+            "Kills: {}",
+            if kills.is_empty() {
+                "none".to_string()
+            } else {
+                kills.join(", ")
+            }
+        ),
+        format!("Best combo: {}", score.best_combo),
+        format!("Reached wave {}", director.wave),
+        format!("Survived {}:{:02}", seconds / 60, seconds % 60),
+    ];
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            OnGameOverScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "Game Over",
+                            TextStyle {
+                                font_size: 80.0,
+                                color: TEXT_COLOR,
+                                ..default()
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(30.0)),
+                            ..default()
+                        }),
+                    );
+                    for line in summary {
+                        parent.spawn(TextBundle::from_section(line, summary_text_style.clone()));
+                    }
+                    for (action, text) in [
+                        (MenuButtonAction::Retry, "Retry"),
+                        (MenuButtonAction::NewRun, "New Run"),
+                        (MenuButtonAction::QuitToMainMenu, "Main Menu"),
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    text,
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
+                });
+            // Next to the summary, with this run highlighted if it made the table
+            spawn_high_score_table(parent, &high_scores, summary_text_style, PRESSED_BUTTON);
+        });
+}
+
 fn settings_menu_setup(mut commands: Commands) {
     let button_style = Style {
         width: Val::Px(200.0),
@@ -328,7 +623,7 @@ fn settings_menu_setup(mut commands: Commands) {
                     for (action, text) in [
                         (MenuButtonAction::SettingsDisplay, "Display"),
                         (MenuButtonAction::SettingsSound, "Sound"),
-                        (MenuButtonAction::BackToMainMenu, "Back"),
+                        (MenuButtonAction::BackFromSettings, "Back"),
                     ] {
                         parent
                             .spawn((
@@ -350,7 +645,7 @@ fn settings_menu_setup(mut commands: Commands) {
         });
 }
 
-fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<DisplayQuality>) {
+fn display_settings_menu_setup(mut commands: Commands, settings: Res<Settings>) {
     let button_style = Style {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -432,7 +727,7 @@ fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<Disp
                                         button_text_style.clone(),
                                     ));
                                 });
-                                if *display_quality == quality_setting {
+                                if settings.display_quality == quality_setting {
                                     entity.insert(SelectedOption);
                                 }
                             }
@@ -454,7 +749,7 @@ fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<Disp
         });
 }
 
-fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
+fn sound_settings_menu_setup(mut commands: Commands, settings: Res<Settings>) {
     let button_style = Style {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -509,7 +804,7 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                                 "Volume",
                                 button_text_style.clone(),
                             ));
-                            for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
+                            for volume_setting in 0..=MAX_VOLUME {
                                 let mut entity = parent.spawn((
                                     ButtonBundle {
                                         style: Style {
@@ -522,7 +817,7 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                                     },
                                     Volume(volume_setting),
                                 ));
-                                if *volume == Volume(volume_setting) {
+                                if settings.volume == Volume(volume_setting) {
                                     entity.insert(SelectedOption);
                                 }
                             }
@@ -552,6 +847,8 @@ fn menu_action(
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
+    current_game_state: Res<State<GameState>>,
+    mut reset_run_events: EventWriter<ResetRun>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -570,7 +867,33 @@ fn menu_action(
                 MenuButtonAction::SettingsSound => {
                     menu_state.set(MenuState::SettingsSound);
                 }
-                MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
+                MenuButtonAction::BackFromSettings => {
+                    if *current_game_state.get() == GameState::Paused {
+                        menu_state.set(MenuState::Pause);
+                    } else {
+                        menu_state.set(MenuState::Main);
+                    }
+                }
+                MenuButtonAction::Resume => game_state.set(GameState::InGame),
+                MenuButtonAction::Retry => {
+                    reset_run_events.send(ResetRun {
+                        seed: RunSeed::Same,
+                        then: GameState::InGame,
+                    });
+                }
+                MenuButtonAction::NewRun => {
+                    reset_run_events.send(ResetRun {
+                        seed: RunSeed::New,
+                        then: GameState::InGame,
+                    });
+                }
+                // Leaves a fresh world behind the menu, ready for "New Game"
+                MenuButtonAction::QuitToMainMenu => {
+                    reset_run_events.send(ResetRun {
+                        seed: RunSeed::New,
+                        then: GameState::Menu,
+                    });
+                }
                 MenuButtonAction::BackToSettings => {
                     menu_state.set(MenuState::Settings);
                 }
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{schedule::InGameSet, state::GameState};
+
+// Gameplay and Rapier both advance in steps of exactly this many seconds, whatever the frame rate
+pub const FIXED_TIMESTEP: f64 = 1.0 / 60.0;
+
+pub fn rapier_configuration() -> RapierConfiguration {
+    RapierConfiguration {
+        gravity: Vec3::ZERO,
+        timestep_mode: TimestepMode::Fixed {
+            dt: FIXED_TIMESTEP as f32,
+            substeps: 1,
+        },
+        ..default()
+    }
+}
+
+// Physics poses from the last two fixed steps. Rendered frames blend between them so motion
+// stays smooth when the frame rate and the fixed rate don't line up.
+#[derive(Component, Debug, Default)]
+pub struct InterpolatedTransform {
+    previous: Option<(Vec3, Quat)>,
+    current: Option<(Vec3, Quat)>,
+}
+
+// Runs in `Update` before anything that follows a rigid body on screen (e.g. the camera)
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub struct TransformInterpolationSet;
+
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_seconds(FIXED_TIMESTEP))
+            .add_systems(FixedFirst, restore_physics_transforms)
+            .add_systems(
+                FixedUpdate,
+                clear_external_forces
+                    .after(InGameSet::DespawnEntities)
+                    .before(InGameSet::UserInput),
+            )
+            .add_systems(
+                FixedLast,
+                (track_new_rigid_bodies, record_physics_transforms).chain(),
+            )
+            .add_systems(
+                Update,
+                interpolate_transforms.in_set(TransformInterpolationSet),
+            )
+            .add_systems(OnEnter(GameState::Paused), suspend_physics)
+            .add_systems(OnExit(GameState::Paused), resume_physics);
+    }
+}
+
+// Bodies keep their velocities through a pause, they just stop moving until it ends
+fn suspend_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+// Forces are re-applied from scratch every step, so nothing builds up between steps
+fn clear_external_forces(mut query: Query<&mut ExternalForce>) {
+    for mut ext_force in query.iter_mut() {
+        *ext_force = ExternalForce::default();
+    }
+}
+
+fn track_new_rigid_bodies(
+    mut commands: Commands,
+    query: Query<Entity, (Added<RigidBody>, Without<InterpolatedTransform>)>,
+) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .insert(InterpolatedTransform::default());
+    }
+}
+
+fn record_physics_transforms(mut query: Query<(&Transform, &mut InterpolatedTransform)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        let pose = (transform.translation, transform.rotation);
+        interpolated.previous = interpolated.current.or(Some(pose));
+        interpolated.current = Some(pose);
+    }
+}
+
+// Puts the real physics pose back before the next step, so gameplay and Rapier never see a
+// blended one
+fn restore_physics_transforms(mut query: Query<(&mut Transform, &InterpolatedTransform)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        if let Some((translation, rotation)) = interpolated.current {
+            transform.translation = translation;
+            transform.rotation = rotation;
+        }
+    }
+}
+
+fn interpolate_transforms(
+    mut query: Query<(&mut Transform, &InterpolatedTransform)>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let t = fixed_time.overstep_fraction();
+    for (mut transform, interpolated) in query.iter_mut() {
+        let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) else {
+            continue;
+        };
+        transform.translation = previous.0.lerp(current.0, t);
+        transform.rotation = previous.1.slerp(current.1, t);
+    }
+}
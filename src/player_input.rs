@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+use crate::schedule::InGameSet;
+
+const WEAPON_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+// Everything the player asked the ship to do this frame. Gameplay systems read this instead of
+// the keyboard, so a recorded replay drives the ship exactly like a person would.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerActions {
+    pub forward: bool,
+    pub backward: bool,
+    pub turn_left: bool,
+    pub turn_right: bool,
+    pub roll_left: bool,
+    pub roll_right: bool,
+    pub fire: bool,
+    pub shield: bool,
+    // 1-based weapon to switch to, or 0 to keep the current one
+    pub weapon_slot: u8,
+}
+
+impl PlayerActions {
+    pub fn to_bits(self) -> u32 {
+        [
+            self.forward,
+            self.backward,
+            self.turn_left,
+            self.turn_right,
+            self.roll_left,
+            self.roll_right,
+            self.fire,
+            self.shield,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (i, &set)| bits | (u32::from(set) << i))
+            | (u32::from(self.weapon_slot) << 8)
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        let bit = |i: u32| bits & (1 << i) != 0;
+        Self {
+            forward: bit(0),
+            backward: bit(1),
+            turn_left: bit(2),
+            turn_right: bit(3),
+            roll_left: bit(4),
+            roll_right: bit(5),
+            fire: bit(6),
+            shield: bit(7),
+            weapon_slot: ((bits >> 8) & 0xf) as u8,
+        }
+    }
+
+    fn from_keyboard(keyboard_input: &ButtonInput<KeyCode>) -> Self {
+        Self {
+            forward: keyboard_input.pressed(KeyCode::KeyW),
+            backward: keyboard_input.pressed(KeyCode::KeyS),
+            turn_left: keyboard_input.pressed(KeyCode::KeyA),
+            turn_right: keyboard_input.pressed(KeyCode::KeyD),
+            roll_left: keyboard_input.pressed(KeyCode::ShiftLeft),
+            roll_right: keyboard_input.pressed(KeyCode::ControlLeft),
+            fire: keyboard_input.pressed(KeyCode::Space),
+            shield: keyboard_input.pressed(KeyCode::Tab),
+            weapon_slot: WEAPON_KEYS
+                .iter()
+                .position(|&key| keyboard_input.pressed(key))
+                .map_or(0, |i| i as u8 + 1),
+        }
+    }
+}
+
+// Where `PlayerActions` comes from. Anything other than the keyboard is driven by the replay module.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputSource {
+    #[default]
+    Keyboard,
+    Playback,
+}
+
+// Runs inside `InGameSet::UserInput`, before anything that reads `PlayerActions`
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub struct PlayerActionsSet;
+
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerActions>()
+            .init_resource::<InputSource>()
+            .configure_sets(FixedUpdate, PlayerActionsSet.in_set(InGameSet::UserInput))
+            .add_systems(
+                FixedUpdate,
+                read_keyboard_actions
+                    .run_if(resource_equals(InputSource::Keyboard))
+                    .in_set(PlayerActionsSet),
+            );
+    }
+}
+
+fn read_keyboard_actions(
+    mut actions: ResMut<PlayerActions>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    *actions = PlayerActions::from_keyboard(&keyboard_input);
+}
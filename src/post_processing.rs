@@ -6,9 +6,7 @@ use bevy::{
     ecs::query::QueryItem,
     prelude::*,
     render::{
-        extract_component::{
-            ComponentUniforms, ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin,
-        },
+        extract_component::{ComponentUniforms, ExtractComponentPlugin, UniformComponentPlugin},
         render_graph::{
             NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
         },
@@ -23,6 +21,8 @@ use bevy::{
     },
 };
 
+use crate::tuning::Tuning;
+
 pub struct PostProcessPlugin;
 
 impl Plugin for PostProcessPlugin {
@@ -39,7 +39,11 @@ impl Plugin for PostProcessPlugin {
             // This plugin will prepare the component for the GPU by creating a uniform buffer
             // and writing the data to that buffer every frame.
             UniformComponentPlugin::<PostProcessSettings>::default(),
-        ));
+        ))
+        .add_systems(
+            Update,
+            apply_intensity_tuning.run_if(resource_changed::<Tuning>),
+        );
 
         // We need to get the render app from the main app
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
@@ -272,8 +276,30 @@ impl FromWorld for PostProcessPipeline {
     }
 }
 
-// This is the component that will get passed to the shader
-#[derive(Component, Default, Clone, Copy, ExtractComponent, ShaderType)]
-pub struct PostProcessSettings {
-    pub intensity: f32,
+fn apply_intensity_tuning(
+    mut settings_query: Query<&mut PostProcessSettings>,
+    tuning: Res<Tuning>,
+) {
+    for mut settings in settings_query.iter_mut() {
+        settings.intensity = tuning.post_process_intensity;
+    }
+}
+
+pub use settings::PostProcessSettings;
+
+// `ShaderType` generates a size check function next to the struct rather than on it, which
+// newer compilers flag as unused, so the allow has to cover the whole module
+mod settings {
+    #![allow(dead_code)]
+
+    use bevy::{
+        prelude::*,
+        render::{extract_component::ExtractComponent, render_resource::ShaderType},
+    };
+
+    // This is the component that will get passed to the shader
+    #[derive(Component, Default, Clone, Copy, ExtractComponent, ShaderType)]
+    pub struct PostProcessSettings {
+        pub intensity: f32,
+    }
 }
@@ -0,0 +1,263 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, LineWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    cli::arg_value,
+    player_input::{InputSource, PlayerActions, PlayerActionsSet},
+    rng::GameRng,
+    schedule::InGameSet,
+    state::{RunReset, RunResetSet},
+};
+
+const REPLAY_HEADER: &str = "jumpy-replay 2";
+// Older frames still decode, but they were recorded against a different simulation (weapons,
+// RNG streams and collision layers have all changed since), so they would play out differently
+const OLD_REPLAY_HEADERS: [&str; 1] = ["jumpy-replay 1"];
+
+// A recorded session: the seed the world was generated from, then one line of actions per
+// `InGame` fixed step. Plain text so it can be attached to a bug report and diffed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<PlayerActions>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut lines = text.lines();
+        match lines.next() {
+            Some(REPLAY_HEADER) => {}
+            Some(header) if OLD_REPLAY_HEADERS.contains(&header) => {
+                return Err(invalid(format!(
+                    "`{}` replays were recorded by an older version whose simulation no longer \
+                     matches, so they can't be played back (expected `{}`)",
+                    header, REPLAY_HEADER
+                )));
+            }
+            _ => return Err(invalid(format!("missing `{}` header", REPLAY_HEADER))),
+        }
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| invalid("missing seed".to_string()))?;
+        let frames = lines
+            .enumerate()
+            .map(|(i, line)| {
+                u32::from_str_radix(line, 16)
+                    .map(PlayerActions::from_bits)
+                    .map_err(|err| invalid(format!("frame {}: {}", i, err)))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { seed, frames })
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", REPLAY_HEADER)?;
+        writeln!(f, "seed {}", self.seed)?;
+        for actions in &self.frames {
+            writeln!(f, "{:x}", actions.to_bits())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl ReplayPlayback {
+    pub fn finished(&self) -> bool {
+        self.cursor >= self.replay.frames.len()
+    }
+}
+
+// Streams frames to disk as they happen, so a crash still leaves a usable repro file. The file
+// only ever holds the latest run, and starts over whenever the run is reset.
+#[derive(Resource, Debug)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    writer: LineWriter<File>,
+}
+
+impl ReplayRecorder {
+    pub fn create(path: impl AsRef<Path>, seed: u64) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut writer = LineWriter::new(File::create(&path)?);
+        writeln!(writer, "{}", REPLAY_HEADER)?;
+        writeln!(writer, "seed {}", seed)?;
+        Ok(Self { path, writer })
+    }
+}
+
+// Reseeds the world and hands player input over to `replay`. Must run before `Startup`.
+pub fn start_playback(world: &mut World, replay: Replay) {
+    world.resource_mut::<GameRng>().reseed(replay.seed);
+    *world.resource_mut::<InputSource>() = InputSource::Playback;
+    world.insert_resource(ReplayPlayback { replay, cursor: 0 });
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<ReplayPlayback>() {
+            if let Some(path) = arg_value("--replay") {
+                match Replay::load(&path) {
+                    Ok(replay) => {
+                        info!("Playing back {} ({} frames)", path, replay.frames.len());
+                        start_playback(&mut app.world, replay);
+                    }
+                    Err(err) => error!("Failed to load replay {}: {}", path, err),
+                }
+            }
+        }
+        if !app.world.contains_resource::<ReplayRecorder>() {
+            if let Some(path) = arg_value("--record") {
+                let seed = app.world.resource::<GameRng>().seed();
+                match ReplayRecorder::create(&path, seed) {
+                    Ok(recorder) => {
+                        info!("Recording input to {}", path);
+                        app.insert_resource(recorder);
+                    }
+                    Err(err) => error!("Failed to create replay {}: {}", path, err),
+                }
+            }
+        }
+
+        app.add_systems(
+            FixedUpdate,
+            (
+                play_back_actions
+                    .run_if(resource_equals(InputSource::Playback))
+                    .in_set(PlayerActionsSet),
+                record_actions
+                    .run_if(resource_exists::<ReplayRecorder>)
+                    .after(PlayerActionsSet)
+                    .in_set(InGameSet::UserInput),
+            ),
+        )
+        .add_systems(RunReset, restart_replay.in_set(RunResetSet::Reset));
+    }
+}
+
+fn play_back_actions(
+    mut actions: ResMut<PlayerActions>,
+    mut input_source: ResMut<InputSource>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let Some(mut playback) = playback else {
+        *input_source = InputSource::Keyboard;
+        return;
+    };
+    if playback.finished() {
+        // Hand control back to the player. The replay is kept so a retry can play it again.
+        info!("Replay finished after {} frames", playback.cursor);
+        *actions = PlayerActions::default();
+        *input_source = InputSource::Keyboard;
+        return;
+    }
+    *actions = playback.replay.frames[playback.cursor];
+    playback.cursor += 1;
+}
+
+fn record_actions(
+    mut commands: Commands,
+    mut recorder: ResMut<ReplayRecorder>,
+    actions: Res<PlayerActions>,
+) {
+    if let Err(err) = writeln!(recorder.writer, "{:x}", actions.to_bits()) {
+        error!("Stopped recording replay: {}", err);
+        commands.remove_resource::<ReplayRecorder>();
+    }
+}
+
+// Retrying the same seed plays the replay again from the start, and a new seed ends it. A
+// recording starts a fresh file with the seed of the new run.
+fn restart_replay(
+    mut commands: Commands,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut input_source: ResMut<InputSource>,
+    game_rng: Res<GameRng>,
+) {
+    let seed = game_rng.seed();
+    if let Some(mut playback) = playback {
+        if playback.replay.seed == seed {
+            playback.cursor = 0;
+            *input_source = InputSource::Playback;
+        } else {
+            info!("Stopped playing back the replay, the new run has a different seed");
+            *input_source = InputSource::Keyboard;
+            commands.remove_resource::<ReplayPlayback>();
+        }
+    }
+    if let Some(mut recorder) = recorder {
+        match ReplayRecorder::create(&recorder.path, seed) {
+            Ok(restarted) => *recorder = restarted,
+            Err(err) => {
+                warn!("Stopped recording replay: {}", err);
+                commands.remove_resource::<ReplayRecorder>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_round_trips_through_text() {
+        let replay = Replay {
+            seed: 1234,
+            frames: vec![
+                PlayerActions::default(),
+                PlayerActions {
+                    forward: true,
+                    turn_left: true,
+                    fire: true,
+                    ..default()
+                },
+                PlayerActions {
+                    backward: true,
+                    roll_right: true,
+                    shield: true,
+                    weapon_slot: 3,
+                    ..default()
+                },
+            ],
+        };
+        assert_eq!(Replay::parse(&replay.to_string()).unwrap(), replay);
+    }
+
+    #[test]
+    fn rejects_text_without_header() {
+        assert!(Replay::parse("seed 1\n0\n").is_err());
+    }
+
+    #[test]
+    fn rejects_version_1_replays() {
+        let err = Replay::parse("jumpy-replay 1\nseed 1\n0\n").unwrap_err();
+        assert!(err.to_string().contains("no longer matches"));
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::cli::arg_value;
+
+// Each subsystem draws from its own stream, so e.g. firing an extra enemy walk never shifts
+// where the next asteroid spawns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    Asteroids,
+    Enemies,
+    Behaviors,
+    Planets,
+    Stars,
+    Background,
+}
+
+#[derive(Resource, Debug)]
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<RngStream, StdRng>,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    // Picks a seed from `--seed <u64>` on the command line, falling back to entropy.
+    pub fn from_args() -> Self {
+        match arg_value("--seed").map(|seed| seed.parse::<u64>()) {
+            Some(Ok(seed)) => Self::new(seed),
+            Some(Err(err)) => {
+                warn!("Ignoring invalid --seed: {}", err);
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Restarts every stream from a new seed
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.streams.clear();
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams
+            .entry(stream)
+            .or_insert_with(|| StdRng::seed_from_u64(seed ^ stream.salt()))
+    }
+
+    // Splits off an independent generator, for work that outlives the borrow of the resource
+    pub fn fork(&mut self, stream: RngStream) -> StdRng {
+        StdRng::seed_from_u64(self.stream(stream).gen())
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::thread_rng().gen())
+    }
+}
+
+impl RngStream {
+    fn salt(self) -> u64 {
+        // Arbitrary odd constants, just far enough apart that the streams never line up
+        match self {
+            RngStream::Asteroids => 0x9E37_79B9_7F4A_7C15,
+            RngStream::Enemies => 0xC2B2_AE3D_27D4_EB4F,
+            RngStream::Behaviors => 0x1656_67B1_9E37_79F9,
+            RngStream::Planets => 0x27D4_EB2F_1656_67C5,
+            RngStream::Stars => 0x85EB_CA77_C2B2_AE63,
+            RngStream::Background => 0xFF51_AFD7_ED55_8CCD,
+        }
+    }
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // Keep a seed that was inserted before the plugin (e.g. by the headless app)
+        if !app.world.contains_resource::<GameRng>() {
+            app.insert_resource(GameRng::from_args());
+        }
+        app.add_systems(Startup, log_seed);
+    }
+}
+
+fn log_seed(rng: Res<GameRng>) {
+    info!("Game seed: {}", rng.seed());
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
 
 use crate::state::GameState;
 
@@ -15,7 +16,7 @@ pub struct SchedulePlugin;
 impl Plugin for SchedulePlugin {
     fn build(&self, app: &mut App) {
         app.configure_sets(
-            Update,
+            FixedUpdate,
             (
                 InGameSet::DespawnEntities,
                 // Flush commands (i.e. `apply_deferred` runs)
@@ -24,10 +25,11 @@ impl Plugin for SchedulePlugin {
                 InGameSet::CollisionDetection,
             )
                 .chain()
+                .before(PhysicsSet::SyncBackend)
                 .run_if(in_state(GameState::InGame)),
         )
         .add_systems(
-            Update,
+            FixedUpdate,
             apply_deferred
                 .after(InGameSet::DespawnEntities)
                 .before(InGameSet::EntityUpdates),
@@ -0,0 +1,274 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{
+    archetype::ArchetypeKind,
+    asset_loader::{parse_embedded, RonResource},
+    despawn::Killed,
+    schedule::InGameSet,
+    state::{RunReset, RunResetSet},
+};
+
+// Points and combo rules, from `assets/config/score.ron`
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoreTable {
+    pub points: HashMap<ArchetypeKind, u64>,
+    pub survival_points_per_second: f32,
+    pub combo_window_seconds: f32,
+    pub kills_per_step: u32,
+    pub max_multiplier: u32,
+}
+
+impl Default for ScoreTable {
+    fn default() -> Self {
+        parse_embedded(include_str!("../assets/config/score.ron"))
+    }
+}
+
+impl RonResource for ScoreTable {
+    const PATH: &'static str = "config/score.ron";
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if !(self.survival_points_per_second.is_finite() && self.survival_points_per_second >= 0.0)
+        {
+            problems.push(format!(
+                "survival_points_per_second must not be negative, got {}",
+                self.survival_points_per_second
+            ));
+        }
+        if !(self.combo_window_seconds.is_finite() && self.combo_window_seconds > 0.0) {
+            problems.push(format!(
+                "combo_window_seconds must be positive, got {}",
+                self.combo_window_seconds
+            ));
+        }
+        if self.kills_per_step == 0 {
+            problems.push("kills_per_step must be at least 1".to_string());
+        }
+        if self.max_multiplier == 0 {
+            problems.push("max_multiplier must be at least 1".to_string());
+        }
+        problems
+    }
+}
+
+impl ScoreTable {
+    pub fn multiplier(&self, combo: u32) -> u32 {
+        (1 + combo / self.kills_per_step.max(1)).min(self.max_multiplier.max(1))
+    }
+}
+
+// The current run. Stays put through `GameOver` so it can be shown, and is cleared when the
+// next run starts.
+#[derive(Resource, Debug, Clone)]
+pub struct Score {
+    pub kill_points: u64,
+    pub survival_points: f32,
+    pub survival_seconds: f32,
+    pub kills: HashMap<ArchetypeKind, u32>,
+    // Kills in the current combo
+    pub combo: u32,
+    pub best_combo: u32,
+    pub multiplier: u32,
+    // Until the current combo runs out
+    pub combo_seconds_left: f32,
+    // How the player's ship went down, once it has
+    pub death: Option<Killed>,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            kill_points: 0,
+            survival_points: 0.0,
+            survival_seconds: 0.0,
+            kills: HashMap::new(),
+            combo: 0,
+            best_combo: 0,
+            multiplier: 1,
+            combo_seconds_left: 0.0,
+            death: None,
+        }
+    }
+}
+
+impl Score {
+    pub fn points(&self) -> u64 {
+        self.kill_points + self.survival_points as u64
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+}
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .add_systems(
+                FixedUpdate,
+                (score_survival, score_kills)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(RunReset, reset_score.in_set(RunResetSet::Reset));
+    }
+}
+
+fn score_survival(mut score: ResMut<Score>, table: Res<ScoreTable>, time: Res<Time>) {
+    let delta = time.delta_seconds();
+    score.survival_seconds += delta;
+    score.survival_points += delta * table.survival_points_per_second;
+    score.combo_seconds_left -= delta;
+    if score.combo_seconds_left <= 0.0 {
+        score.combo = 0;
+        score.multiplier = 1;
+    }
+}
+
+fn score_kills(
+    mut killed_events: EventReader<Killed>,
+    mut score: ResMut<Score>,
+    table: Res<ScoreTable>,
+) {
+    for killed in killed_events.read() {
+        if killed.entity_kind == ArchetypeKind::Spaceship {
+            score.death = Some(*killed);
+            continue;
+        }
+        let by_player = killed
+            .by
+            .is_some_and(|hit| hit.attacker == ArchetypeKind::Spaceship);
+        if !by_player {
+            continue;
+        }
+        *score.kills.entry(killed.entity_kind).or_default() += 1;
+        score.combo += 1;
+        score.best_combo = score.best_combo.max(score.combo);
+        score.multiplier = table.multiplier(score.combo);
+        score.combo_seconds_left = table.combo_window_seconds;
+        let points = table.points.get(&killed.entity_kind).copied().unwrap_or(0);
+        score.kill_points += points * score.multiplier as u64;
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::damage::{DamageKind, LastHit};
+
+    fn score_app() -> App {
+        let mut app = App::new();
+        app.add_event::<Killed>()
+            .init_resource::<Score>()
+            .init_resource::<ScoreTable>()
+            .init_resource::<Time>()
+            .add_systems(Update, (score_survival, score_kills).chain());
+        app
+    }
+
+    fn killed(app: &mut App, entity_kind: ArchetypeKind, attacker: Option<ArchetypeKind>) {
+        app.world.send_event(Killed {
+            entity: Entity::PLACEHOLDER,
+            entity_kind,
+            position: Vec3::ZERO,
+            by: attacker.map(|attacker| LastHit {
+                attacker,
+                damage_kind: DamageKind::Kinetic,
+            }),
+        });
+    }
+
+    #[test]
+    fn multiplier_steps_up_and_caps() {
+        let mut table = ScoreTable {
+            kills_per_step: 3,
+            max_multiplier: 4,
+            ..default()
+        };
+        let multipliers: Vec<_> = [0, 2, 3, 5, 6, 9, 100]
+            .into_iter()
+            .map(|combo| table.multiplier(combo))
+            .collect();
+        assert_eq!(multipliers, vec![1, 1, 2, 2, 3, 4, 4]);
+
+        // Treated as one kill per step rather than dividing by zero
+        table.kills_per_step = 0;
+        assert_eq!(table.multiplier(2), 3);
+        assert_eq!(table.multiplier(50), 4);
+    }
+
+    #[test]
+    fn only_the_player_scores_kills() {
+        let mut app = score_app();
+        killed(
+            &mut app,
+            ArchetypeKind::Enemy,
+            Some(ArchetypeKind::Spaceship),
+        );
+        killed(&mut app, ArchetypeKind::Enemy, Some(ArchetypeKind::Enemy));
+        killed(&mut app, ArchetypeKind::Asteroid, None);
+        app.update();
+
+        let score = app.world.resource::<Score>();
+        assert_eq!(score.kill_points, 100);
+        assert_eq!(score.total_kills(), 1);
+        assert_eq!(score.combo, 1);
+        assert!(score.death.is_none());
+    }
+
+    #[test]
+    fn ships_death_is_recorded_not_scored() {
+        let mut app = score_app();
+        killed(
+            &mut app,
+            ArchetypeKind::Spaceship,
+            Some(ArchetypeKind::Asteroid),
+        );
+        app.update();
+
+        let score = app.world.resource::<Score>();
+        assert_eq!(score.kill_points, 0);
+        assert_eq!(score.total_kills(), 0);
+        let death = score.death.unwrap();
+        assert_eq!(death.entity_kind, ArchetypeKind::Spaceship);
+        assert_eq!(death.by.unwrap().attacker, ArchetypeKind::Asteroid);
+    }
+
+    #[test]
+    fn combo_runs_out_after_the_window() {
+        let mut app = score_app();
+        let window = app.world.resource::<ScoreTable>().combo_window_seconds;
+        for _ in 0..3 {
+            killed(
+                &mut app,
+                ArchetypeKind::Enemy,
+                Some(ArchetypeKind::Spaceship),
+            );
+        }
+        app.update();
+        assert_eq!(app.world.resource::<Score>().multiplier, 2);
+
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(window * 0.5));
+        app.update();
+        assert_eq!(app.world.resource::<Score>().combo, 3);
+
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(window * 0.6));
+        app.update();
+        let score = app.world.resource::<Score>();
+        assert_eq!((score.combo, score.multiplier, score.best_combo), (0, 1, 3));
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::{core_pipeline::bloom::BloomSettings, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::user_data::{load_user_data, save_user_data};
+
+const SETTINGS_FILE: &str = "settings.ron";
+pub const MAX_VOLUME: u32 = 9;
+
+#[derive(Serialize, Deserialize, Debug, Default, Component, PartialEq, Eq, Clone, Copy)]
+pub enum DisplayQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+// What a `DisplayQuality` turns into once it reaches the renderer
+pub struct QualityProfile {
+    pub msaa: Msaa,
+    // `None` turns bloom off
+    pub bloom: Option<BloomSettings>,
+    // Share of `Tuning::star_count` that is spawned
+    pub star_fraction: f32,
+    // Only this many stars carry a point light
+    pub star_lights: usize,
+    // Width of one skybox face, in pixels
+    pub skybox_size: u32,
+    pub post_process: bool,
+}
+
+impl DisplayQuality {
+    pub fn profile(self) -> QualityProfile {
+        match self {
+            DisplayQuality::Low => QualityProfile {
+                msaa: Msaa::Off,
+                bloom: None,
+                star_fraction: 0.25,
+                star_lights: 0,
+                skybox_size: 256,
+                post_process: false,
+            },
+            DisplayQuality::Medium => QualityProfile {
+                msaa: Msaa::Sample4,
+                bloom: Some(BloomSettings {
+                    intensity: 0.1,
+                    ..BloomSettings::NATURAL
+                }),
+                star_fraction: 0.5,
+                star_lights: 64,
+                skybox_size: 512,
+                post_process: true,
+            },
+            DisplayQuality::High => QualityProfile {
+                msaa: Msaa::Sample4,
+                bloom: Some(BloomSettings::NATURAL),
+                star_fraction: 1.0,
+                star_lights: usize::MAX,
+                skybox_size: 1000,
+                post_process: true,
+            },
+        }
+    }
+}
+
+// Steps from 0 (muted) to `MAX_VOLUME`
+#[derive(Serialize, Deserialize, Debug, Component, PartialEq, Eq, Clone, Copy)]
+pub struct Volume(pub u32);
+
+impl Volume {
+    pub fn gain(self) -> f32 {
+        self.0.min(MAX_VOLUME) as f32 / MAX_VOLUME as f32
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self(5)
+    }
+}
+
+// Everything the player can change from the settings menu, kept in the user's data directory
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub display_quality: DisplayQuality,
+    pub volume: Volume,
+}
+
+// Kept out of `GamePlugins`, so the headless app never touches the user's files
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut settings = load_user_data::<Settings>(SETTINGS_FILE).unwrap_or_default();
+        // A hand-edited file can ask for more than the menu has buttons for
+        settings.volume.0 = settings.volume.0.min(MAX_VOLUME);
+        app.insert_resource(settings)
+            .insert_resource(settings.display_quality.profile().msaa)
+            .add_systems(
+                Update,
+                (apply_msaa, save_settings).run_if(resource_changed::<Settings>),
+            );
+    }
+}
+
+fn apply_msaa(mut msaa: ResMut<Msaa>, settings: Res<Settings>) {
+    msaa.set_if_neq(settings.display_quality.profile().msaa);
+}
+
+fn save_settings(settings: Res<Settings>) {
+    // Just loaded, nothing new to write
+    if settings.is_added() {
+        return;
+    }
+    save_user_data(SETTINGS_FILE, &*settings);
+}
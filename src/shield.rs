@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    archetype::Archetypes,
+    player_input::{PlayerActions, PlayerActionsSet},
+    schedule::InGameSet,
+    spaceship::Spaceship,
+};
+
+// An empty shield has to charge back up to this fraction before it can be raised again,
+// otherwise holding the key makes it flicker on and off
+const MIN_RAISE_FRACTION: f32 = 0.2;
+const BUBBLE_RADIUS: f32 = 4.0;
+const BUBBLE_COLOR: Color = Color::rgba(0.3, 0.6, 1.0, 0.25);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ShieldStats {
+    pub capacity: f32,
+    pub drain_per_second: f32,
+    pub recharge_per_second: f32,
+    // Seconds after the shield drops before it starts to recharge
+    pub recharge_delay: f32,
+    // Fraction of incoming damage the shield soaks up while it is raised
+    pub damage_reduction: f32,
+}
+
+// The shield generator and its energy pool. Present whether or not the shield is raised.
+#[derive(Component, Debug)]
+pub struct Shield {
+    pub stats: ShieldStats,
+    pub energy: f32,
+    recharge_delay: Timer,
+}
+
+impl Shield {
+    pub fn new(stats: ShieldStats) -> Self {
+        Self {
+            stats,
+            energy: stats.capacity,
+            recharge_delay: Timer::from_seconds(stats.recharge_delay, TimerMode::Once),
+        }
+    }
+
+    // Swaps in retuned stats without refilling or draining the pool
+    pub fn restat(&mut self, stats: ShieldStats) {
+        self.stats = stats;
+        self.energy = self.energy.min(stats.capacity);
+        self.recharge_delay
+            .set_duration(Duration::from_secs_f32(stats.recharge_delay));
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.stats.capacity > 0.0 {
+            self.energy / self.stats.capacity
+        } else {
+            0.0
+        }
+    }
+}
+
+// Present only while the shield is raised
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpaceShipShield {
+    pub damage_reduction: f32,
+}
+
+#[derive(Component, Debug)]
+struct ShieldBubble;
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                restat_shields.run_if(resource_changed::<Archetypes>),
+                spaceship_shield_controls,
+            )
+                .chain()
+                .after(PlayerActionsSet)
+                .in_set(InGameSet::UserInput),
+        )
+        .add_systems(
+            Update,
+            show_shield_bubbles.run_if(resource_exists::<Assets<StandardMaterial>>),
+        );
+    }
+}
+
+// Applies an edited spaceship archetype to the shield the ship already has
+fn restat_shields(
+    mut query: Query<(&mut Shield, Option<&mut SpaceShipShield>), With<Spaceship>>,
+    archetypes: Res<Archetypes>,
+) {
+    let Some(stats) = archetypes.spaceship.shield else {
+        return;
+    };
+    for (mut shield, raised) in query.iter_mut() {
+        if shield.stats == stats {
+            continue;
+        }
+        shield.restat(stats);
+        if let Some(mut raised) = raised {
+            raised.damage_reduction = stats.damage_reduction;
+        }
+    }
+}
+
+fn spaceship_shield_controls(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Shield, Has<SpaceShipShield>), With<Spaceship>>,
+    actions: Res<PlayerActions>,
+    time: Res<Time>,
+) {
+    let Ok((spaceship, mut shield, raised)) = query.get_single_mut() else {
+        return;
+    };
+    let can_raise = raised || shield.fraction() >= MIN_RAISE_FRACTION;
+    let keep_raised = actions.shield && can_raise && shield.energy > 0.0;
+
+    if keep_raised {
+        shield.energy =
+            (shield.energy - shield.stats.drain_per_second * time.delta_seconds()).max(0.0);
+        shield.recharge_delay.reset();
+    } else if shield.recharge_delay.tick(time.delta()).finished() {
+        shield.energy = (shield.energy + shield.stats.recharge_per_second * time.delta_seconds())
+            .min(shield.stats.capacity);
+    }
+
+    if keep_raised && !raised {
+        commands.entity(spaceship).insert(SpaceShipShield {
+            damage_reduction: shield.stats.damage_reduction,
+        });
+    } else if !keep_raised && raised {
+        commands.entity(spaceship).remove::<SpaceShipShield>();
+    }
+}
+
+fn show_shield_bubbles(
+    mut commands: Commands,
+    raised_query: Query<Entity, Added<SpaceShipShield>>,
+    mut lowered: RemovedComponents<SpaceShipShield>,
+    bubble_query: Query<(Entity, &Parent), With<ShieldBubble>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut bubble_assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+) {
+    for entity in lowered.read() {
+        for (bubble, parent) in bubble_query.iter() {
+            if parent.get() == entity {
+                commands.entity(bubble).despawn_recursive();
+            }
+        }
+    }
+    for entity in raised_query.iter() {
+        let (mesh, material) = bubble_assets
+            .get_or_insert_with(|| {
+                (
+                    meshes.add(Sphere::new(BUBBLE_RADIUS)),
+                    materials.add(StandardMaterial {
+                        base_color: BUBBLE_COLOR,
+                        emissive: BUBBLE_COLOR * 2.0,
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    }),
+                )
+            })
+            .clone();
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                PbrBundle {
+                    mesh,
+                    material,
+                    ..default()
+                },
+                ShieldBubble,
+            ));
+        });
+    }
+}
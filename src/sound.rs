@@ -0,0 +1,464 @@
+use bevy::{
+    audio::{AddAudioSource, DefaultSpatialScale, SpatialScale, Volume as AudioVolume},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use crate::{
+    archetype::ArchetypeKind,
+    asset_loader::{parse_embedded, RonResource},
+    camera::MainCamera,
+    damage::DamageEvent,
+    despawn::Killed,
+    enemy::Enemy,
+    settings::Settings,
+    shield::SpaceShipShield,
+    state::GameState,
+    synth::{self, Synth},
+    waves::{WaveDirector, WavePhase},
+    weapons::Projectile,
+};
+
+// Distance between the listener's ears, in world units
+const EAR_GAP: f32 = 20.0;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    MissileFire,
+    Impact,
+    Explosion,
+    ShieldUp,
+    EnemySpawn,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 5] = [
+        Sfx::MissileFire,
+        Sfx::Impact,
+        Sfx::Explosion,
+        Sfx::ShieldUp,
+        Sfx::EnemySpawn,
+    ];
+
+    fn synth(self) -> Synth {
+        match self {
+            Sfx::MissileFire => synth::missile_fire(),
+            Sfx::Impact => synth::impact(),
+            Sfx::Explosion => synth::explosion(),
+            Sfx::ShieldUp => synth::shield_up(),
+            Sfx::EnemySpawn => synth::enemy_spawn(),
+        }
+    }
+}
+
+// The music is a stack of loops that are faded in and out to suit what is going on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MusicLayer {
+    Drone,
+    Pulse,
+    // Only while a wave is being fought
+    Drive,
+    Lament,
+}
+
+impl MusicLayer {
+    pub const ALL: [MusicLayer; 4] = [
+        MusicLayer::Drone,
+        MusicLayer::Pulse,
+        MusicLayer::Drive,
+        MusicLayer::Lament,
+    ];
+
+    fn synth(self) -> Synth {
+        match self {
+            MusicLayer::Drone => synth::drone(),
+            MusicLayer::Pulse => synth::pulse(),
+            MusicLayer::Drive => synth::drive(),
+            MusicLayer::Lament => synth::lament(),
+        }
+    }
+
+    // How loud this layer should be, before the volume settings
+    pub fn target(self, state: GameState, wave_phase: WavePhase) -> f32 {
+        match (state, self) {
+            (GameState::Menu, MusicLayer::Drone) => 1.0,
+            (GameState::InGame, MusicLayer::Drone) => 0.5,
+            (GameState::InGame, MusicLayer::Pulse) => 1.0,
+            (GameState::InGame, MusicLayer::Drive) if wave_phase == WavePhase::Active => 1.0,
+            // The game keeps humming under the pause menu
+            (GameState::Paused, MusicLayer::Drone | MusicLayer::Pulse) => 0.4,
+            (GameState::GameOver, MusicLayer::Lament) => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SfxMix {
+    pub gain: f32,
+    pub min_interval: f32,
+}
+
+impl Default for SfxMix {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            min_interval: 0.0,
+        }
+    }
+}
+
+// Bus levels and per-sound limits, from `assets/config/audio.ron`
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct AudioTable {
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub full_volume_distance: f32,
+    pub audible_threshold: f32,
+    pub music_fade_seconds: f32,
+    pub sfx: HashMap<Sfx, SfxMix>,
+}
+
+impl Default for AudioTable {
+    fn default() -> Self {
+        parse_embedded(include_str!("../assets/config/audio.ron"))
+    }
+}
+
+impl RonResource for AudioTable {
+    const PATH: &'static str = "config/audio.ron";
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        for (name, level) in [
+            ("sfx_volume", self.sfx_volume),
+            ("music_volume", self.music_volume),
+            ("audible_threshold", self.audible_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&level) {
+                problems.push(format!("{} must be between 0 and 1, got {}", name, level));
+            }
+        }
+        for (name, value) in [
+            ("full_volume_distance", self.full_volume_distance),
+            ("music_fade_seconds", self.music_fade_seconds),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                problems.push(format!("{} must be positive, got {}", name, value));
+            }
+        }
+        for (sfx, mix) in self.sfx.iter() {
+            if !(mix.gain.is_finite() && mix.gain >= 0.0) {
+                problems.push(format!("{:?} gain must not be negative", sfx));
+            }
+            if !(mix.min_interval.is_finite() && mix.min_interval >= 0.0) {
+                problems.push(format!("{:?} min_interval must not be negative", sfx));
+            }
+        }
+        problems
+    }
+}
+
+impl AudioTable {
+    pub fn sfx(&self, sfx: Sfx) -> SfxMix {
+        self.sfx.get(&sfx).copied().unwrap_or_default()
+    }
+
+    // How much of a sound is left `distance` away from the listener. Matches the falloff the
+    // output applies when it plays the sound.
+    pub fn falloff(&self, distance: f32) -> f32 {
+        let scaled = distance / self.full_volume_distance;
+        (1.0 / (scaled * scaled)).min(1.0)
+    }
+}
+
+// Something in the world that should make a sound
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct SoundCue {
+    pub sfx: Sfx,
+    pub position: Vec3,
+}
+
+// A cue that made it through the mixer, at its final volume before distance falloff
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct Voice {
+    pub sfx: Sfx,
+    pub position: Vec3,
+    pub gain: f32,
+}
+
+// How loud each music layer currently is, faded towards its target. The volume settings are
+// applied on top.
+#[derive(Resource, Debug, Default)]
+pub struct MusicMix {
+    levels: HashMap<MusicLayer, f32>,
+}
+
+impl MusicMix {
+    pub fn level(&self, layer: MusicLayer) -> f32 {
+        self.levels.get(&layer).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBackend {
+    // Plays through Bevy's audio output, which needs `DefaultPlugins`
+    #[default]
+    Output,
+    // Mixes as usual but plays nothing, for headless runs
+    Null,
+}
+
+// Every voice the null backend was asked to play
+#[derive(Resource, Debug, Default)]
+pub struct NullAudioLog {
+    pub voices: Vec<Voice>,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub enum SoundSet {
+    Cues,
+    Mix,
+    Playback,
+}
+
+#[derive(Resource, Debug)]
+struct SoundBank {
+    sfx: HashMap<Sfx, Handle<Synth>>,
+}
+
+#[derive(Component, Debug)]
+struct MusicTrack(MusicLayer);
+
+#[derive(Default)]
+pub struct SoundPlugin {
+    pub backend: AudioBackend,
+}
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoundCue>()
+            .add_event::<Voice>()
+            .init_resource::<MusicMix>()
+            .configure_sets(
+                Update,
+                (SoundSet::Cues, SoundSet::Mix, SoundSet::Playback).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    cue_missile_fire,
+                    cue_impacts,
+                    cue_explosions,
+                    cue_shields,
+                    cue_enemy_spawns,
+                )
+                    .in_set(SoundSet::Cues),
+            )
+            .add_systems(Update, (mix_sfx, mix_music).in_set(SoundSet::Mix));
+
+        match self.backend {
+            AudioBackend::Output => {
+                app.add_audio_source::<Synth>()
+                    .add_systems(Startup, (build_sound_bank, start_music).chain())
+                    .add_systems(
+                        Update,
+                        (
+                            add_camera_listener,
+                            apply_spatial_scale.run_if(resource_changed::<AudioTable>),
+                            play_voices,
+                            apply_music_levels,
+                        )
+                            .chain()
+                            .in_set(SoundSet::Playback),
+                    );
+            }
+            AudioBackend::Null => {
+                app.init_resource::<NullAudioLog>()
+                    .add_systems(Update, log_voices.in_set(SoundSet::Playback));
+            }
+        }
+    }
+}
+
+fn cue_missile_fire(query: Query<&Transform, Added<Projectile>>, mut cues: EventWriter<SoundCue>) {
+    for transform in query.iter() {
+        cues.send(SoundCue {
+            sfx: Sfx::MissileFire,
+            position: transform.translation,
+        });
+    }
+}
+
+fn cue_impacts(
+    mut damage_events: EventReader<DamageEvent>,
+    query: Query<&GlobalTransform>,
+    mut cues: EventWriter<SoundCue>,
+) {
+    for damage in damage_events.read() {
+        if let Ok(transform) = query.get(damage.target) {
+            cues.send(SoundCue {
+                sfx: Sfx::Impact,
+                position: transform.translation(),
+            });
+        }
+    }
+}
+
+fn cue_explosions(mut killed_events: EventReader<Killed>, mut cues: EventWriter<SoundCue>) {
+    for killed in killed_events.read() {
+        // A missile going off is already heard as its impact
+        if killed.entity_kind == ArchetypeKind::Missile {
+            continue;
+        }
+        cues.send(SoundCue {
+            sfx: Sfx::Explosion,
+            position: killed.position,
+        });
+    }
+}
+
+fn cue_shields(query: Query<&Transform, Added<SpaceShipShield>>, mut cues: EventWriter<SoundCue>) {
+    for transform in query.iter() {
+        cues.send(SoundCue {
+            sfx: Sfx::ShieldUp,
+            position: transform.translation,
+        });
+    }
+}
+
+fn cue_enemy_spawns(query: Query<&Transform, Added<Enemy>>, mut cues: EventWriter<SoundCue>) {
+    for transform in query.iter() {
+        cues.send(SoundCue {
+            sfx: Sfx::EnemySpawn,
+            position: transform.translation,
+        });
+    }
+}
+
+// Applies the volume settings, drops sounds too far away to hear, and stops the same sound
+// from stacking up when many of its cues land together
+fn mix_sfx(
+    mut cues: EventReader<SoundCue>,
+    mut voices: EventWriter<Voice>,
+    listener_query: Query<&GlobalTransform, With<MainCamera>>,
+    table: Res<AudioTable>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut last_played: Local<HashMap<Sfx, f32>>,
+) {
+    let bus = settings.volume.gain() * table.sfx_volume;
+    // Without a camera there is nothing to be far from
+    let listener = listener_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation());
+    let now = time.elapsed_seconds();
+    for cue in cues.read() {
+        let mix = table.sfx(cue.sfx);
+        let gain = bus * mix.gain;
+        let falloff = listener.map_or(1.0, |listener| {
+            table.falloff(listener.distance(cue.position))
+        });
+        if gain * falloff < table.audible_threshold {
+            continue;
+        }
+        if let Some(&last) = last_played.get(&cue.sfx) {
+            if now - last < mix.min_interval {
+                continue;
+            }
+        }
+        last_played.insert(cue.sfx, now);
+        voices.send(Voice {
+            sfx: cue.sfx,
+            position: cue.position,
+            gain,
+        });
+    }
+}
+
+// Real time, so the music still fades while the game is paused
+fn mix_music(
+    mut mix: ResMut<MusicMix>,
+    state: Res<State<GameState>>,
+    director: Res<WaveDirector>,
+    table: Res<AudioTable>,
+    time: Res<Time<Real>>,
+) {
+    let step = time.delta_seconds() / table.music_fade_seconds;
+    for layer in MusicLayer::ALL {
+        let target = layer.target(*state.get(), director.phase);
+        let level = mix.levels.entry(layer).or_default();
+        *level += (target - *level).clamp(-step, step);
+    }
+}
+
+fn log_voices(mut voices: EventReader<Voice>, mut log: ResMut<NullAudioLog>) {
+    log.voices.extend(voices.read().copied());
+}
+
+fn build_sound_bank(mut commands: Commands, mut synths: ResMut<Assets<Synth>>) {
+    let sfx = Sfx::ALL
+        .into_iter()
+        .map(|sfx| (sfx, synths.add(sfx.synth())))
+        .collect();
+    commands.insert_resource(SoundBank { sfx });
+}
+
+// Every layer plays all the time, silent until the mix turns it up, so they stay in step
+fn start_music(mut commands: Commands, mut synths: ResMut<Assets<Synth>>) {
+    for layer in MusicLayer::ALL {
+        commands.spawn((
+            AudioSourceBundle {
+                source: synths.add(layer.synth()),
+                settings: PlaybackSettings::LOOP.with_volume(AudioVolume::new(0.0)),
+            },
+            MusicTrack(layer),
+        ));
+    }
+}
+
+fn add_camera_listener(
+    mut commands: Commands,
+    camera_query: Query<Entity, (With<MainCamera>, Without<SpatialListener>)>,
+) {
+    for camera in camera_query.iter() {
+        commands
+            .entity(camera)
+            .insert(SpatialListener::new(EAR_GAP));
+    }
+}
+
+fn apply_spatial_scale(mut scale: ResMut<DefaultSpatialScale>, table: Res<AudioTable>) {
+    scale.0 = SpatialScale::new(1.0 / table.full_volume_distance);
+}
+
+fn play_voices(mut commands: Commands, mut voices: EventReader<Voice>, bank: Res<SoundBank>) {
+    for voice in voices.read() {
+        let Some(source) = bank.sfx.get(&voice.sfx) else {
+            continue;
+        };
+        commands.spawn((
+            AudioSourceBundle {
+                source: source.clone(),
+                settings: PlaybackSettings::DESPAWN
+                    .with_spatial(true)
+                    .with_volume(AudioVolume::new(voice.gain)),
+            },
+            TransformBundle::from_transform(Transform::from_translation(voice.position)),
+        ));
+    }
+}
+
+fn apply_music_levels(
+    track_query: Query<(&MusicTrack, &AudioSink)>,
+    mix: Res<MusicMix>,
+    table: Res<AudioTable>,
+    settings: Res<Settings>,
+) {
+    let bus = settings.volume.gain() * table.music_volume;
+    for (track, sink) in track_query.iter() {
+        sink.set_volume(mix.level(track.0) * bus);
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+
+use crate::{schedule::InGameSet, state::GameState};
+
+const CELL_SIZE: f32 = 50.0;
+
+// Uniform grid over the XZ plane holding every rigid body's position as of the start of the
+// current fixed step. Neighbor queries only visit the cells a search circle overlaps.
+#[derive(Resource, Debug)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec3)>>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec3) {
+        self.cells
+            .entry(self.cell_of(position))
+            .or_default()
+            .push((entity, position));
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.values().all(Vec::is_empty)
+    }
+
+    // Everything within `radius` of `center` on the XZ plane, in no particular order
+    pub fn within(&self, center: Vec3, radius: f32) -> impl Iterator<Item = (Entity, Vec3)> + '_ {
+        let min = self.cell_of(center - Vec3::new(radius, 0.0, radius));
+        let max = self.cell_of(center + Vec3::new(radius, 0.0, radius));
+        // A huge (or infinite) radius covers more cells than are occupied, so walk the occupied
+        // ones instead of the whole box
+        let span = (i64::from(max.x) - i64::from(min.x) + 1)
+            .saturating_mul(i64::from(max.y) - i64::from(min.y) + 1);
+        let scan_all = span > self.cells.len() as i64;
+        let boxed = (!scan_all)
+            .then(|| {
+                (min.x..=max.x)
+                    .flat_map(move |x| (min.y..=max.y).map(move |z| IVec2::new(x, z)))
+                    .filter_map(|cell| self.cells.get(&cell))
+            })
+            .into_iter()
+            .flatten();
+        let occupied = scan_all.then(|| self.cells.values()).into_iter().flatten();
+        let radius_squared = radius * radius;
+        boxed
+            .chain(occupied)
+            .flatten()
+            .copied()
+            .filter(move |(_, position)| {
+                Vec2::new(position.x - center.x, position.z - center.z).length_squared()
+                    <= radius_squared
+            })
+    }
+
+    fn cell_of(&self, position: Vec3) -> IVec2 {
+        IVec2::new(
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+// Runs before anything in `InGameSet::UserInput`, so every later system sees a fresh grid
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub struct SpatialGridSet;
+
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid>()
+            .configure_sets(
+                FixedUpdate,
+                SpatialGridSet
+                    .after(InGameSet::DespawnEntities)
+                    .before(InGameSet::UserInput)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(FixedUpdate, rebuild_spatial_grid.in_set(SpatialGridSet));
+    }
+}
+
+fn rebuild_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    query: Query<(Entity, &Transform), With<RigidBody>>,
+) {
+    grid.clear();
+    for (entity, transform) in query.iter() {
+        grid.insert(entity, transform.translation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn random_grid(count: u32) -> (SpatialGrid, Vec<(Entity, Vec3)>) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut grid = SpatialGrid::default();
+        let points: Vec<_> = (0..count)
+            .map(|index| {
+                let position = Vec3::new(
+                    rng.gen_range(-1000.0..1000.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-1000.0..1000.0),
+                );
+                (Entity::from_raw(index), position)
+            })
+            .collect();
+        for &(entity, position) in &points {
+            grid.insert(entity, position);
+        }
+        (grid, points)
+    }
+
+    fn sorted(mut found: Vec<(Entity, Vec3)>) -> Vec<Entity> {
+        found.sort_by_key(|(entity, _)| *entity);
+        found.into_iter().map(|(entity, _)| entity).collect()
+    }
+
+    #[test]
+    fn within_matches_brute_force() {
+        let (grid, points) = random_grid(500);
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..50 {
+            let center = Vec3::new(
+                rng.gen_range(-1200.0..1200.0),
+                0.0,
+                rng.gen_range(-1200.0..1200.0),
+            );
+            let radius = rng.gen_range(0.0..400.0);
+            let expected = points
+                .iter()
+                .copied()
+                .filter(|(_, position)| {
+                    Vec2::new(position.x - center.x, position.z - center.z).length() <= radius
+                })
+                .collect();
+            assert_eq!(
+                sorted(grid.within(center, radius).collect()),
+                sorted(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn huge_radius_finds_everything_without_walking_the_box() {
+        let (grid, points) = random_grid(100);
+        for radius in [1e9, f32::MAX, f32::INFINITY] {
+            assert_eq!(
+                sorted(grid.within(Vec3::ZERO, radius).collect()),
+                sorted(points.clone())
+            );
+        }
+    }
+}
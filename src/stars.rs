@@ -4,7 +4,12 @@ use bevy::prelude::*;
 
 use rand::Rng;
 
-const STAR_COUNT: usize = 2500;
+use crate::{
+    rng::{GameRng, RngStream},
+    settings::Settings,
+    tuning::Tuning,
+};
+
 const STAR_SPAWN_RANGE_X: Range<f32> = -500.0..500.0;
 const STAR_SPAWN_RANGE_Y: Range<f32> = -250.0..250.0;
 const STAR_SPAWN_RANGE_Z: Range<f32> = -500.0..500.0;
@@ -21,17 +26,32 @@ pub struct StarsPlugin;
 
 impl Plugin for StarsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, spawn_stars);
+        app.add_systems(PostStartup, spawn_stars).add_systems(
+            Update,
+            respawn_stars_on_budget_change
+                .run_if(resource_changed::<Tuning>.or_else(resource_changed::<Settings>)),
+        );
     }
 }
 
+// How many stars to spawn, and how many of those carry a point light
+fn star_budget(tuning: &Tuning, settings: &Settings) -> (usize, usize) {
+    let profile = settings.display_quality.profile();
+    let stars = (tuning.star_count as f32 * profile.star_fraction).round() as usize;
+    (stars, profile.star_lights.min(stars))
+}
+
 fn spawn_stars(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut game_rng: ResMut<GameRng>,
+    tuning: Res<Tuning>,
+    settings: Res<Settings>,
 ) {
-    let mut rng = rand::thread_rng();
-    for _ in 0..STAR_COUNT {
+    let (stars, lights) = star_budget(&tuning, &settings);
+    let rng = game_rng.stream(RngStream::Stars);
+    for index in 0..stars {
         let translation = Vec3::new(
             rng.gen_range(STAR_SPAWN_RANGE_X),
             rng.gen_range(STAR_SPAWN_RANGE_Y),
@@ -43,17 +63,17 @@ fn spawn_stars(
             emissive: BASE_COLOR * size, // bigger stars are brighter
             ..default()
         });
-        commands
-            .spawn((
-                PbrBundle {
-                    transform,
-                    mesh: meshes.add(Sphere::new(size)),
-                    material: material.clone(),
-                    ..default()
-                },
-                Star,
-            ))
-            .with_children(|children| {
+        let mut star = commands.spawn((
+            PbrBundle {
+                transform,
+                mesh: meshes.add(Sphere::new(size)),
+                material: material.clone(),
+                ..default()
+            },
+            Star,
+        ));
+        if index < lights {
+            star.with_children(|children| {
                 children.spawn(PointLightBundle {
                     point_light: PointLight {
                         intensity: POINT_LIGHT_INTENSITY,
@@ -64,5 +84,31 @@ fn spawn_stars(
                     ..default()
                 });
             });
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn respawn_stars_on_budget_change(
+    mut commands: Commands,
+    star_query: Query<Entity, With<Star>>,
+    light_query: Query<&Parent, With<PointLight>>,
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<StandardMaterial>>,
+    game_rng: ResMut<GameRng>,
+    tuning: Res<Tuning>,
+    settings: Res<Settings>,
+) {
+    // Other point lights in the scene aren't part of the budget
+    let lights = light_query
+        .iter()
+        .filter(|parent| star_query.contains(parent.get()))
+        .count();
+    if (star_query.iter().len(), lights) == star_budget(&tuning, &settings) {
+        return;
+    }
+    for star in star_query.iter() {
+        commands.entity(star).despawn_recursive();
     }
+    spawn_stars(commands, meshes, materials, game_rng, tuning, settings);
 }
@@ -1,26 +1,64 @@
-use bevy::prelude::*;
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use rand::Rng;
+
+use crate::rng::GameRng;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
     Splash,
+    // Waits for models and archetypes before anything is spawned
+    Loading,
     Menu,
     InGame,
     Paused,
+    // Waits on the game over screen until the player picks what to do next
     GameOver,
 }
 
+// Puts the world back to the start of a run. Runs once when loading finishes, and again for
+// every `ResetRun`. Plugins add their part to one of the `RunResetSet`s.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RunReset;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RunResetSet {
+    // Clears out whatever is left of the last run
+    Despawn,
+    // Run bookkeeping, e.g. score and waves
+    Reset,
+    Spawn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunSeed {
+    // Replays the same world as the run that just ended
+    Same,
+    New,
+}
+
+// Asks for a fresh run, after which the game moves on to `then`
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetRun {
+    pub seed: RunSeed,
+    pub then: GameState,
+}
+
 pub struct StatePlugin;
 
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<GameState>().add_systems(
-            Update,
-            (
-                game_state_input_events,
-                transition_to_in_game.run_if(in_state(GameState::GameOver)),
-            ),
-        );
+        app.init_state::<GameState>()
+            .add_event::<ResetRun>()
+            .init_schedule(RunReset)
+            .configure_sets(
+                RunReset,
+                (RunResetSet::Despawn, RunResetSet::Reset, RunResetSet::Spawn).chain(),
+            )
+            .add_systems(Update, (game_state_input_events, reset_run_on_request))
+            .add_systems(OnExit(GameState::Loading), run_reset)
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            .add_systems(OnExit(GameState::Paused), unpause_time);
     }
 }
 
@@ -38,6 +76,32 @@ fn game_state_input_events(
     }
 }
 
-fn transition_to_in_game(mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::InGame);
+fn run_reset(world: &mut World) {
+    world.run_schedule(RunReset);
+}
+
+fn reset_run_on_request(world: &mut World) {
+    let Some(request) = world.resource_mut::<Events<ResetRun>>().drain().last() else {
+        return;
+    };
+    let mut game_rng = world.resource_mut::<GameRng>();
+    let seed = match request.seed {
+        RunSeed::Same => game_rng.seed(),
+        RunSeed::New => rand::thread_rng().gen(),
+    };
+    info!("Starting a new run with seed {}", seed);
+    game_rng.reseed(seed);
+    run_reset(world);
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(request.then);
+}
+
+// Stops `FixedUpdate` from running at all, and anything in `Update` that scales by the delta
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
 }
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+// Reynolds-style steering on the XZ plane. Every behavior returns the force that would turn the
+// current velocity into the one it wants. Add up the ones that apply, weighted, and pass the sum
+// through `SteeringLimits::limit` before it goes into an `ExternalForce`.
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SteeringLimits {
+    pub max_speed: f32,
+    pub max_force: f32,
+}
+
+impl SteeringLimits {
+    pub fn limit(&self, force: Vec3) -> Vec3 {
+        flatten(force).clamp_length_max(self.max_force)
+    }
+
+    // Force toward `desired` velocity, which is itself capped at `max_speed`
+    pub fn toward_velocity(&self, velocity: Vec3, desired: Vec3) -> Vec3 {
+        self.limit(flatten(desired).clamp_length_max(self.max_speed) - flatten(velocity))
+    }
+}
+
+pub fn flatten(vector: Vec3) -> Vec3 {
+    Vec3::new(vector.x, 0.0, vector.z)
+}
+
+// Full speed toward `target`. Brakes to a stop when already on top of it.
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, limits: &SteeringLimits) -> Vec3 {
+    let desired = flatten(target - position).normalize_or_zero() * limits.max_speed;
+    limits.toward_velocity(velocity, desired)
+}
+
+// Like `seek`, but slows down linearly inside `slowing_radius` so it stops at `target`
+pub fn arrive(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    slowing_radius: f32,
+    limits: &SteeringLimits,
+) -> Vec3 {
+    let offset = flatten(target - position);
+    let speed = if slowing_radius > 0.0 {
+        limits.max_speed * (offset.length() / slowing_radius).min(1.0)
+    } else {
+        limits.max_speed
+    };
+    limits.toward_velocity(velocity, offset.normalize_or_zero() * speed)
+}
+
+pub fn flee(position: Vec3, velocity: Vec3, threat: Vec3, limits: &SteeringLimits) -> Vec3 {
+    let desired = flatten(position - threat).normalize_or_zero() * limits.max_speed;
+    limits.toward_velocity(velocity, desired)
+}
+
+// Where a target will be by the time something at `max_speed` could reach where it is now
+fn predict(position: Vec3, target: Vec3, target_velocity: Vec3, max_speed: f32) -> Vec3 {
+    let look_ahead = if max_speed > 0.0 {
+        flatten(target - position).length() / max_speed
+    } else {
+        0.0
+    };
+    target + flatten(target_velocity) * look_ahead
+}
+
+pub fn pursue(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    limits: &SteeringLimits,
+) -> Vec3 {
+    let predicted = predict(position, target, target_velocity, limits.max_speed);
+    seek(position, velocity, predicted, limits)
+}
+
+pub fn evade(
+    position: Vec3,
+    velocity: Vec3,
+    threat: Vec3,
+    threat_velocity: Vec3,
+    limits: &SteeringLimits,
+) -> Vec3 {
+    let predicted = predict(position, threat, threat_velocity, limits.max_speed);
+    flee(position, velocity, predicted, limits)
+}
+
+// Pushes sideways away from the nearest obstacle, given as `(center, radius)`, that the current
+// heading would run into within `look_ahead` seconds
+pub fn avoid_obstacles(
+    position: Vec3,
+    velocity: Vec3,
+    obstacles: impl IntoIterator<Item = (Vec3, f32)>,
+    look_ahead: f32,
+    limits: &SteeringLimits,
+) -> Vec3 {
+    let velocity = flatten(velocity);
+    let Some(heading) = velocity.try_normalize() else {
+        return Vec3::ZERO;
+    };
+    let reach = velocity.length() * look_ahead;
+    let nearest = obstacles
+        .into_iter()
+        .filter_map(|(center, radius)| {
+            let offset = flatten(center - position);
+            let ahead = offset.dot(heading);
+            if ahead < 0.0 || ahead > reach + radius {
+                return None;
+            }
+            let lateral = offset - heading * ahead;
+            (lateral.length() < radius).then_some((ahead, lateral))
+        })
+        .min_by(|(a, _), (b, _)| a.total_cmp(b));
+    let Some((_, lateral)) = nearest else {
+        return Vec3::ZERO;
+    };
+    // Dead ahead has no better side, so pick one
+    let away = (-lateral)
+        .try_normalize()
+        .unwrap_or_else(|| Vec3::Y.cross(heading));
+    away * limits.max_force
+}
+
+// Boids. Neighbors are `(position, velocity)` pairs and should not include the entity itself.
+
+// Away from neighbors, more strongly from the closest ones
+pub fn separation(
+    position: Vec3,
+    velocity: Vec3,
+    neighbors: &[(Vec3, Vec3)],
+    limits: &SteeringLimits,
+) -> Vec3 {
+    let push: Vec3 = neighbors
+        .iter()
+        .filter_map(|(neighbor, _)| {
+            let away = flatten(position - *neighbor);
+            let distance_squared = away.length_squared();
+            (distance_squared > 0.0).then(|| away / distance_squared)
+        })
+        .sum();
+    match push.try_normalize() {
+        Some(direction) => limits.toward_velocity(velocity, direction * limits.max_speed),
+        None => Vec3::ZERO,
+    }
+}
+
+// Toward the neighbors' average heading
+pub fn alignment(velocity: Vec3, neighbors: &[(Vec3, Vec3)], limits: &SteeringLimits) -> Vec3 {
+    let heading: Vec3 = neighbors
+        .iter()
+        .map(|(_, neighbor_velocity)| flatten(*neighbor_velocity))
+        .sum();
+    match heading.try_normalize() {
+        Some(direction) => limits.toward_velocity(velocity, direction * limits.max_speed),
+        None => Vec3::ZERO,
+    }
+}
+
+// Toward the neighbors' center
+pub fn cohesion(
+    position: Vec3,
+    velocity: Vec3,
+    neighbors: &[(Vec3, Vec3)],
+    limits: &SteeringLimits,
+) -> Vec3 {
+    if neighbors.is_empty() {
+        return Vec3::ZERO;
+    }
+    let center = neighbors
+        .iter()
+        .map(|(neighbor, _)| *neighbor)
+        .sum::<Vec3>()
+        / neighbors.len() as f32;
+    seek(position, velocity, center, limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: SteeringLimits = SteeringLimits {
+        max_speed: 10.0,
+        max_force: 20.0,
+    };
+
+    #[test]
+    fn seek_on_top_of_target_is_finite() {
+        let target = Vec3::new(3.0, 0.0, 4.0);
+        assert_eq!(seek(target, Vec3::ZERO, target, &LIMITS), Vec3::ZERO);
+        let braking = seek(target, Vec3::new(2.0, 0.0, 0.0), target, &LIMITS);
+        assert!(braking.is_finite());
+        assert_eq!(braking, Vec3::new(-2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn arrive_stops_at_target() {
+        let target = Vec3::new(100.0, 0.0, -50.0);
+        let (mut position, mut velocity) = (Vec3::ZERO, Vec3::ZERO);
+        let dt = 1.0 / 60.0;
+        for _ in 0..60 * 120 {
+            velocity += arrive(position, velocity, target, 100.0, &LIMITS) * dt;
+            position += velocity * dt;
+        }
+        assert!(position.distance(target) < 0.1, "{}", position);
+        assert!(velocity.length() < 0.01, "{}", velocity);
+        assert_eq!(
+            arrive(target, Vec3::ZERO, target, 100.0, &LIMITS),
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn limit_never_exceeds_max_force() {
+        for force in [
+            Vec3::new(1e9, 5.0, -1e9),
+            Vec3::new(0.0, 1e6, 0.0),
+            Vec3::new(-30.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 4.0),
+        ] {
+            let limited = LIMITS.limit(force);
+            assert!(limited.length() <= LIMITS.max_force + 1e-3, "{}", limited);
+            assert_eq!(limited.y, 0.0);
+        }
+        assert_eq!(
+            LIMITS.limit(Vec3::new(3.0, 0.0, 4.0)),
+            Vec3::new(3.0, 0.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn avoid_obstacles_pushes_sideways_from_planet_ahead() {
+        let velocity = Vec3::new(10.0, 0.0, 0.0);
+        let planet = (Vec3::new(50.0, 0.0, 0.0), 10.0);
+        let push = avoid_obstacles(Vec3::ZERO, velocity, [planet], 10.0, &LIMITS);
+        assert!((push.length() - LIMITS.max_force).abs() < 1e-3, "{}", push);
+        assert!(push.dot(velocity).abs() < 1e-3, "{}", push);
+
+        // Slightly to one side, it pushes toward the other
+        let planet = (Vec3::new(50.0, 0.0, 2.0), 10.0);
+        let push = avoid_obstacles(Vec3::ZERO, velocity, [planet], 10.0, &LIMITS);
+        assert!(push.z < 0.0, "{}", push);
+
+        // Behind, or out of reach, it is ignored
+        let behind = (Vec3::new(-50.0, 0.0, 0.0), 10.0);
+        let far = (Vec3::new(500.0, 0.0, 0.0), 10.0);
+        assert_eq!(
+            avoid_obstacles(Vec3::ZERO, velocity, [behind, far], 10.0, &LIMITS),
+            Vec3::ZERO
+        );
+    }
+}
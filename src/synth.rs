@@ -0,0 +1,220 @@
+use std::{f32::consts::TAU, sync::Arc, time::Duration};
+
+use bevy::{
+    audio::{Decodable, Source},
+    prelude::*,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const SAMPLE_RATE: u32 = 44_100;
+// Music layers all loop over the same four bars, so they stay in step with each other
+const LOOP_SECONDS: f32 = 8.0;
+const BAR_SECONDS: f32 = 2.0;
+// Root of each bar: A, A, F, G
+const BAR_ROOTS: [f32; 4] = [55.0, 55.0, 43.65, 49.0];
+
+// A mono clip rendered in code, there are no audio files to load
+#[derive(Asset, TypePath, Clone)]
+pub struct Synth {
+    samples: Arc<[f32]>,
+}
+
+impl Synth {
+    // Calls `wave` with the time of every sample, in seconds
+    fn render(seconds: f32, mut wave: impl FnMut(f32) -> f32) -> Self {
+        let len = (seconds * SAMPLE_RATE as f32) as usize;
+        Self {
+            samples: (0..len)
+                .map(|index| wave(index as f32 / SAMPLE_RATE as f32).clamp(-1.0, 1.0))
+                .collect(),
+        }
+    }
+}
+
+pub struct SynthDecoder {
+    samples: Arc<[f32]>,
+    index: usize,
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.index).copied();
+        self.index += 1;
+        sample
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.samples.len() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> SynthDecoder {
+        SynthDecoder {
+            samples: self.samples.clone(),
+            index: 0,
+        }
+    }
+}
+
+fn decay(t: f32, rate: f32) -> f32 {
+    (-t * rate).exp()
+}
+
+// Fades in over `attack` and out over the last `release` seconds of a note `length` long
+fn envelope(t: f32, length: f32, attack: f32, release: f32) -> f32 {
+    (t / attack).min((length - t) / release).clamp(0.0, 1.0)
+}
+
+// Sine oscillator whose frequency can change from one sample to the next
+struct Oscillator {
+    phase: f32,
+}
+
+impl Oscillator {
+    fn new() -> Self {
+        Self { phase: 0.0 }
+    }
+
+    fn sine(&mut self, frequency: f32) -> f32 {
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+        (self.phase * TAU).sin()
+    }
+}
+
+// White noise through a one-pole low-pass, `cutoff` from 0 (silent) to 1 (unfiltered)
+struct Rumble {
+    rng: StdRng,
+    value: f32,
+}
+
+impl Rumble {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            value: 0.0,
+        }
+    }
+
+    fn next(&mut self, cutoff: f32) -> f32 {
+        let white: f32 = self.rng.gen_range(-1.0..1.0);
+        self.value += (white - self.value) * cutoff;
+        self.value
+    }
+}
+
+pub fn missile_fire() -> Synth {
+    let mut oscillator = Oscillator::new();
+    let mut hiss = Rumble::new(1);
+    Synth::render(0.18, |t| {
+        let tone = oscillator.sine(900.0 - 3600.0 * t);
+        (tone.signum() * 0.15 + tone * 0.3 + hiss.next(0.6) * 0.2) * decay(t, 18.0)
+    })
+}
+
+pub fn impact() -> Synth {
+    let mut oscillator = Oscillator::new();
+    let mut crack = Rumble::new(2);
+    Synth::render(0.15, |t| {
+        (oscillator.sine(140.0 - 500.0 * t) * 0.6 + crack.next(0.3) * 0.8) * decay(t, 30.0)
+    })
+}
+
+pub fn explosion() -> Synth {
+    let mut oscillator = Oscillator::new();
+    let mut blast = Rumble::new(3);
+    Synth::render(1.2, |t| {
+        // The blast dulls as it dies away
+        let noise = blast.next(0.25 * decay(t, 2.0) + 0.02) * 2.5;
+        let attack = (t / 0.005).min(1.0);
+        attack * (noise * decay(t, 3.5) + oscillator.sine(45.0) * 0.5 * decay(t, 3.0))
+    })
+}
+
+pub fn shield_up() -> Synth {
+    let mut oscillator = Oscillator::new();
+    let length = 0.35;
+    Synth::render(length, |t| {
+        let tremolo = 0.75 + 0.25 * (t * 30.0 * TAU).sin();
+        oscillator.sine(300.0 + 2600.0 * t) * tremolo * 0.5 * envelope(t, length, 0.02, 0.15)
+    })
+}
+
+pub fn enemy_spawn() -> Synth {
+    let mut oscillator = Oscillator::new();
+    let length = 0.5;
+    Synth::render(length, |t| {
+        // Two-tone warble, like an alarm
+        let frequency = [440.0, 660.0][(t / 0.0625) as usize % 2];
+        oscillator.sine(frequency).signum() * 0.2 * envelope(t, length, 0.01, 0.3)
+    })
+}
+
+// Held A minor chord that slowly swells. Every partial fits a whole number of cycles in the loop.
+pub fn drone() -> Synth {
+    Synth::render(LOOP_SECONDS, |t| {
+        let swell = 0.7 + 0.3 * (t * 0.25 * TAU).sin();
+        let chord: f32 = [110.0, 132.0, 165.0, 220.0]
+            .iter()
+            .map(|frequency| (t * frequency * TAU).sin())
+            .sum();
+        chord * 0.12 * swell
+    })
+}
+
+// Eighth-note bass line on the root of each bar
+pub fn pulse() -> Synth {
+    let step = BAR_SECONDS / 8.0;
+    Synth::render(LOOP_SECONDS, |t| {
+        let root = BAR_ROOTS[(t / BAR_SECONDS) as usize % BAR_ROOTS.len()];
+        let t = t % step;
+        let tone = (t * root * TAU).sin() + 0.4 * (t * root * 2.0 * TAU).sin();
+        tone * 0.35 * decay(t, 8.0) * envelope(t, step, 0.005, 0.01)
+    })
+}
+
+// Sixteenth-note arpeggio up the minor chord on each bar's root, two octaves up
+pub fn drive() -> Synth {
+    let step = BAR_SECONDS / 16.0;
+    let intervals = [1.0, 1.2, 1.5, 2.0];
+    Synth::render(LOOP_SECONDS, |t| {
+        let root = BAR_ROOTS[(t / BAR_SECONDS) as usize % BAR_ROOTS.len()] * 4.0;
+        let frequency = root * intervals[(t / step) as usize % intervals.len()];
+        let t = t % step;
+        let tone = (t * frequency * TAU).sin() + 0.3 * (t * frequency * 3.0 * TAU).sin();
+        tone * 0.15 * decay(t, 20.0) * envelope(t, step, 0.003, 0.01)
+    })
+}
+
+// A slow falling line, one note per bar
+pub fn lament() -> Synth {
+    let notes = [220.0, 196.0, 174.6, 164.8];
+    Synth::render(LOOP_SECONDS, |t| {
+        let frequency = notes[(t / BAR_SECONDS) as usize % notes.len()];
+        let t = t % BAR_SECONDS;
+        let vibrato = 1.0 + 0.004 * (t * 5.0 * TAU).sin();
+        let phase = t * frequency * vibrato * TAU;
+        (phase.sin() + 0.3 * (phase * 2.0).sin()) * 0.25 * envelope(t, BAR_SECONDS, 0.3, 0.6)
+    })
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::asset_loader::{parse_embedded, RonResource};
+
+// Past this the frame rate falls off a cliff, since every star carries a point light
+const MAX_STAR_COUNT: usize = 20_000;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CameraTuning {
+    pub distance_init: f32,
+    pub lerp_speed: f32,
+    pub scroll_factor: f32,
+}
+
+// Designer-facing knobs from `assets/config/tuning.ron`. Plugins read this resource instead of
+// their own constants, and react when it changes.
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tuning {
+    pub camera: CameraTuning,
+    pub asteroid_spawn_seconds: f32,
+    pub star_count: usize,
+    pub post_process_intensity: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        parse_embedded(include_str!("../assets/config/tuning.ron"))
+    }
+}
+
+impl RonResource for Tuning {
+    const PATH: &'static str = "config/tuning.ron";
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut positive = |name: &str, value: f32| {
+            if !(value.is_finite() && value > 0.0) {
+                problems.push(format!("{} must be positive, got {}", name, value));
+            }
+        };
+        positive("camera.distance_init", self.camera.distance_init);
+        positive("camera.lerp_speed", self.camera.lerp_speed);
+        positive("camera.scroll_factor", self.camera.scroll_factor);
+        positive("asteroid_spawn_seconds", self.asteroid_spawn_seconds);
+        if !(self.post_process_intensity.is_finite() && self.post_process_intensity >= 0.0) {
+            problems.push(format!(
+                "post_process_intensity must not be negative, got {}",
+                self.post_process_intensity
+            ));
+        }
+        if self.star_count > MAX_STAR_COUNT {
+            problems.push(format!(
+                "star_count must be at most {}, got {}",
+                MAX_STAR_COUNT, self.star_count
+            ));
+        }
+        problems
+    }
+}
@@ -0,0 +1,59 @@
+use std::{env, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Serialize};
+
+const APP_DIR: &str = "jumpy";
+
+// The per-user data directory: `%APPDATA%` on Windows, `~/Library/Application Support` on macOS,
+// and `$XDG_DATA_HOME` or `~/.local/share` everywhere else
+fn data_dir() -> Option<PathBuf> {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(".local/share")))
+    };
+    base.map(|base| base.join(APP_DIR))
+}
+
+pub fn user_data_path(file: &str) -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(file))
+}
+
+// `None` if the file is missing. A file that can't be read is logged and treated the same way.
+pub fn load_user_data<T: DeserializeOwned>(file: &str) -> Option<T> {
+    let path = user_data_path(file)?;
+    let text = fs::read_to_string(&path).ok()?;
+    match ron::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+pub fn save_user_data<T: Serialize>(file: &str, value: &T) {
+    let Some(path) = user_data_path(file) else {
+        warn!("No user data directory, not saving {}", file);
+        return;
+    };
+    let result = ron::ser::to_string_pretty(value, PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|text| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            fs::write(&path, text).map_err(|err| err.to_string())
+        });
+    if let Err(err) = result {
+        error!("Failed to save {}: {}", path.display(), err);
+    }
+}
@@ -0,0 +1,479 @@
+use std::f32::consts::PI;
+
+use bevy::{gizmos::config::GizmoConfigStore, prelude::*};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    archetype::Archetypes,
+    asset_loader::{parse_embedded, RonResource, SceneAssets},
+    collision_layers::LayerTable,
+    enemy::{spawn_enemy, Enemy, EnemyVariant},
+    rng::{GameRng, RngStream},
+    schedule::InGameSet,
+    score::Score,
+    spaceship::Spaceship,
+    state::{RunReset, RunResetSet},
+    weapons::WeaponRegistry,
+};
+
+const TELEGRAPH_COLOR: Color = Color::rgb(1.0, 0.3, 0.2);
+
+// Enemies that share a spawn point
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct EnemyGroup {
+    pub count: u32,
+    #[serde(default)]
+    pub variant: EnemyVariant,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Wave {
+    pub groups: Vec<EnemyGroup>,
+}
+
+// Makes up waves once the scripted ones run out
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Escalation {
+    // Enemies in the first procedural wave, at difficulty 1
+    pub enemies: f32,
+    pub enemies_per_wave: f32,
+    pub max_enemies: u32,
+    pub armed_fraction: f32,
+    pub group_size: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyCurve {
+    pub per_minute: f32,
+    pub per_thousand_points: f32,
+    pub max: f32,
+    // Share of base health enemies gain per point of difficulty above 1
+    pub extra_health: f32,
+}
+
+impl DifficultyCurve {
+    pub fn at(&self, seconds: f32, points: u64) -> f32 {
+        let climb =
+            seconds / 60.0 * self.per_minute + points as f32 / 1000.0 * self.per_thousand_points;
+        (1.0 + climb).min(self.max)
+    }
+}
+
+// Wave script and pacing, from `assets/config/waves.ron`
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct WaveTable {
+    pub first_wave_delay: f32,
+    pub intermission_seconds: f32,
+    pub telegraph_seconds: f32,
+    pub wave_time_limit: f32,
+    pub spawn_distance: (f32, f32),
+    pub group_radius: f32,
+    pub difficulty: DifficultyCurve,
+    pub waves: Vec<Wave>,
+    pub escalation: Escalation,
+}
+
+impl Default for WaveTable {
+    fn default() -> Self {
+        parse_embedded(include_str!("../assets/config/waves.ron"))
+    }
+}
+
+impl RonResource for WaveTable {
+    const PATH: &'static str = "config/waves.ron";
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut positive = |name: &str, value: f32| {
+            if !(value.is_finite() && value > 0.0) {
+                problems.push(format!("{} must be positive, got {}", name, value));
+            }
+        };
+        positive("first_wave_delay", self.first_wave_delay);
+        positive("intermission_seconds", self.intermission_seconds);
+        positive("telegraph_seconds", self.telegraph_seconds);
+        positive("wave_time_limit", self.wave_time_limit);
+        positive("group_radius", self.group_radius);
+        positive("difficulty.max", self.difficulty.max);
+        positive("escalation.enemies", self.escalation.enemies);
+        let mut non_negative = |name: &str, value: f32| {
+            if !(value.is_finite() && value >= 0.0) {
+                problems.push(format!("{} must not be negative, got {}", name, value));
+            }
+        };
+        non_negative("difficulty.per_minute", self.difficulty.per_minute);
+        non_negative(
+            "difficulty.per_thousand_points",
+            self.difficulty.per_thousand_points,
+        );
+        non_negative(
+            "escalation.enemies_per_wave",
+            self.escalation.enemies_per_wave,
+        );
+        if self.escalation.max_enemies == 0 {
+            problems.push("escalation.max_enemies must be at least 1".to_string());
+        }
+        let (near, far) = self.spawn_distance;
+        if !(near >= 0.0 && near < far) {
+            problems.push(format!(
+                "spawn_distance must be (near, far) with 0 <= near < far, got ({}, {})",
+                near, far
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.escalation.armed_fraction) {
+            problems.push(format!(
+                "escalation.armed_fraction must be between 0 and 1, got {}",
+                self.escalation.armed_fraction
+            ));
+        }
+        if self.escalation.group_size == 0 {
+            problems.push("escalation.group_size must be at least 1".to_string());
+        }
+        for (i, wave) in self.waves.iter().enumerate() {
+            if wave.groups.iter().all(|group| group.count == 0) {
+                problems.push(format!("wave {} has no enemies", i + 1));
+            }
+            for group in wave.groups.iter() {
+                let scale = group.variant.health_scale;
+                if !(scale.is_finite() && scale > 0.0) {
+                    problems.push(format!(
+                        "wave {} has a group with health_scale {}",
+                        i + 1,
+                        scale
+                    ));
+                }
+            }
+        }
+        problems
+    }
+}
+
+impl WaveTable {
+    // The groups making up wave `wave`, counting from 1
+    pub fn plan(&self, wave: u32, difficulty: f32) -> Vec<EnemyGroup> {
+        let mut groups = match self.waves.get(wave as usize - 1) {
+            Some(scripted) => scripted.groups.clone(),
+            None => self.escalate(wave - self.waves.len() as u32, difficulty),
+        };
+        let health_scale = 1.0 + (difficulty - 1.0).max(0.0) * self.difficulty.extra_health;
+        for group in groups.iter_mut() {
+            group.variant.health_scale *= health_scale;
+        }
+        groups
+    }
+
+    // `wave` counts procedural waves only, from 1
+    fn escalate(&self, wave: u32, difficulty: f32) -> Vec<EnemyGroup> {
+        let escalation = &self.escalation;
+        let enemies =
+            (escalation.enemies + escalation.enemies_per_wave * (wave - 1) as f32) * difficulty;
+        let total = (enemies.round() as u32).min(escalation.max_enemies);
+        let armed = (total as f32 * escalation.armed_fraction).round() as u32;
+        let mut groups = vec![];
+        for (count, armed) in [(armed, true), (total - armed, false)] {
+            let mut left = count;
+            while left > 0 {
+                let size = left.min(escalation.group_size);
+                groups.push(EnemyGroup {
+                    count: size,
+                    variant: EnemyVariant { armed, ..default() },
+                });
+                left -= size;
+            }
+        }
+        groups
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavePhase {
+    // Counting down to the next wave
+    Intermission,
+    // Spawn points are marked and the enemies are on their way
+    Telegraph,
+    Active,
+}
+
+#[derive(Resource, Debug)]
+pub struct WaveDirector {
+    // The current or most recent wave, 0 before the first one
+    pub wave: u32,
+    pub phase: WavePhase,
+    // Counts down the current phase. During a wave it runs to the time limit.
+    pub timer: Timer,
+    // Seconds in game this run
+    pub elapsed: f32,
+    pub difficulty: f32,
+}
+
+impl WaveDirector {
+    fn new(table: &WaveTable) -> Self {
+        Self {
+            wave: 0,
+            phase: WavePhase::Intermission,
+            timer: Timer::from_seconds(table.first_wave_delay, TimerMode::Once),
+            elapsed: 0.0,
+            difficulty: 1.0,
+        }
+    }
+
+    pub fn seconds_left(&self) -> f32 {
+        self.timer.remaining_secs()
+    }
+}
+
+impl FromWorld for WaveDirector {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(&world.get_resource_or_insert_with(WaveTable::default))
+    }
+}
+
+// For the HUD and anything else that follows the pacing of a run
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum WaveEvent {
+    // Spawn points are marked and `enemies` arrive in `in_seconds`
+    Incoming {
+        wave: u32,
+        enemies: u32,
+        in_seconds: f32,
+    },
+    Started {
+        wave: u32,
+        enemies: u32,
+    },
+    // `cleared` is false when the wave hit its time limit with enemies still alive
+    Ended {
+        wave: u32,
+        cleared: bool,
+    },
+}
+
+// A marked spawn point. The group arrives here when the telegraph runs out.
+#[derive(Component, Debug)]
+pub struct SpawnTelegraph {
+    pub group: EnemyGroup,
+    pub radius: f32,
+}
+
+pub struct WaveDirectorPlugin;
+
+impl Plugin for WaveDirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveDirector>()
+            .add_event::<WaveEvent>()
+            .add_systems(FixedUpdate, advance_waves.in_set(InGameSet::EntityUpdates))
+            .add_systems(
+                Update,
+                draw_spawn_telegraphs.run_if(resource_exists::<GizmoConfigStore>),
+            )
+            .add_systems(RunReset, reset_waves.in_set(RunResetSet::Reset));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_waves(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    mut wave_events: EventWriter<WaveEvent>,
+    enemy_query: Query<(), With<Enemy>>,
+    telegraph_query: Query<(Entity, &Transform, &SpawnTelegraph)>,
+    player_query: Query<&Transform, With<Spaceship>>,
+    table: Res<WaveTable>,
+    score: Res<Score>,
+    time: Res<Time>,
+    archetypes: Res<Archetypes>,
+    scene_assets: Res<SceneAssets>,
+    layer_table: Res<LayerTable>,
+    weapon_registry: Res<WeaponRegistry>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    director.elapsed += time.delta_seconds();
+    director.difficulty = table.difficulty.at(director.elapsed, score.points());
+    director.timer.tick(time.delta());
+    let rng = game_rng.stream(RngStream::Enemies);
+
+    match director.phase {
+        WavePhase::Intermission => {
+            if !director.timer.finished() {
+                return;
+            }
+            director.wave += 1;
+            let groups = table.plan(director.wave, director.difficulty);
+            let (near, far) = table.spawn_distance;
+            for group in groups.iter().filter(|group| group.count > 0) {
+                let angle = rng.gen_range(0.0..PI * 2.0);
+                let distance = rng.gen_range(near..far);
+                let center = player_transform.translation
+                    + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+                commands.spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(center.x, 0.0, center.z)),
+                    SpawnTelegraph {
+                        group: *group,
+                        radius: table.group_radius,
+                    },
+                ));
+            }
+            director.phase = WavePhase::Telegraph;
+            director.timer = Timer::from_seconds(table.telegraph_seconds, TimerMode::Once);
+            wave_events.send(WaveEvent::Incoming {
+                wave: director.wave,
+                enemies: groups.iter().map(|group| group.count).sum(),
+                in_seconds: table.telegraph_seconds,
+            });
+        }
+        WavePhase::Telegraph => {
+            if !director.timer.finished() {
+                return;
+            }
+            let mut enemies = 0;
+            for (entity, transform, telegraph) in telegraph_query.iter() {
+                for _ in 0..telegraph.group.count {
+                    // Uniform over the disc
+                    let angle = rng.gen_range(0.0..PI * 2.0);
+                    let distance = telegraph.radius * rng.gen::<f32>().sqrt();
+                    let offset = Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+                    spawn_enemy(
+                        &mut commands,
+                        transform.translation + offset,
+                        telegraph.group.variant,
+                        &archetypes.enemy,
+                        &scene_assets,
+                        &layer_table,
+                        &weapon_registry,
+                        rng,
+                    );
+                }
+                enemies += telegraph.group.count;
+                commands.entity(entity).despawn_recursive();
+            }
+            info!("Wave {}: spawning {} enemies", director.wave, enemies);
+            director.phase = WavePhase::Active;
+            director.timer = Timer::from_seconds(table.wave_time_limit, TimerMode::Once);
+            wave_events.send(WaveEvent::Started {
+                wave: director.wave,
+                enemies,
+            });
+        }
+        WavePhase::Active => {
+            let cleared = enemy_query.is_empty();
+            if !cleared && !director.timer.finished() {
+                return;
+            }
+            director.phase = WavePhase::Intermission;
+            director.timer = Timer::from_seconds(table.intermission_seconds, TimerMode::Once);
+            wave_events.send(WaveEvent::Ended {
+                wave: director.wave,
+                cleared,
+            });
+        }
+    }
+}
+
+fn reset_waves(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    telegraph_query: Query<Entity, With<SpawnTelegraph>>,
+    table: Res<WaveTable>,
+) {
+    *director = WaveDirector::new(&table);
+    for entity in telegraph_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// A ring at each spawn point, filling in as the enemies get closer to arriving
+fn draw_spawn_telegraphs(
+    mut gizmos: Gizmos,
+    query: Query<(&Transform, &SpawnTelegraph)>,
+    director: Res<WaveDirector>,
+) {
+    let progress = director.timer.fraction();
+    for (transform, telegraph) in query.iter() {
+        let center = transform.translation;
+        gizmos.circle(center, Direction3d::Y, telegraph.radius, TELEGRAPH_COLOR);
+        gizmos.circle(
+            center,
+            Direction3d::Y,
+            telegraph.radius * progress.max(0.05),
+            TELEGRAPH_COLOR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(count: u32, armed: bool) -> EnemyGroup {
+        EnemyGroup {
+            count,
+            variant: EnemyVariant { armed, ..default() },
+        }
+    }
+
+    #[test]
+    fn shipped_table_is_valid() {
+        assert_eq!(WaveTable::default().validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_negative_growth() {
+        let mut table = WaveTable::default();
+        table.difficulty.per_minute = -0.1;
+        table.difficulty.per_thousand_points = f32::NAN;
+        table.escalation.enemies_per_wave = -2.0;
+        table.escalation.max_enemies = 0;
+        assert_eq!(table.validate().len(), 4);
+    }
+
+    #[test]
+    fn escalation_splits_armed_and_unarmed_groups() {
+        let table = WaveTable::default();
+        let first = table.waves.len() as u32 + 1;
+        // 10 enemies, 6 of them armed, in groups of at most 4
+        assert_eq!(
+            table.plan(first, 1.0),
+            vec![group(4, true), group(2, true), group(4, false)]
+        );
+        // Two waves later there are 14, 8 of them armed
+        assert_eq!(
+            table.plan(first + 2, 1.0),
+            vec![
+                group(4, true),
+                group(4, true),
+                group(4, false),
+                group(2, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn escalation_stops_at_max_enemies() {
+        let table = WaveTable::default();
+        let groups = table.escalate(100, 4.0);
+        let total: u32 = groups.iter().map(|group| group.count).sum();
+        assert_eq!(total, table.escalation.max_enemies);
+        let armed: u32 = groups
+            .iter()
+            .filter(|group| group.variant.armed)
+            .map(|group| group.count)
+            .sum();
+        assert_eq!(armed, 24);
+        assert!(groups
+            .iter()
+            .all(|group| group.count <= table.escalation.group_size));
+    }
+
+    #[test]
+    fn plan_scales_health_with_difficulty() {
+        let table = WaveTable::default();
+        let groups = table.plan(1, 3.0);
+        assert_eq!(groups.len(), table.waves[0].groups.len());
+        // 0.5 extra health per point of difficulty above 1
+        assert!(groups.iter().all(|group| group.variant.health_scale == 2.0));
+    }
+}
@@ -0,0 +1,596 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{gizmos::config::GizmoConfigStore, prelude::*};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    archetype::{ArchetypeKind, Archetypes, EntityArchetype},
+    asset_loader::{parse_embedded, RonResource, SceneAssets},
+    collision_detection::CollisionDamage,
+    collision_layers::{CollisionLayer, LayerBundle, LayerTable},
+    damage::{DamageEvent, DamageKind},
+    debug::DebugEntity,
+    enemy::Enemy,
+    health::Health,
+    player_input::{PlayerActions, PlayerActionsSet},
+    schedule::InGameSet,
+    spaceship::{Spaceship, SpaceshipMissile},
+    spatial::SpatialGrid,
+};
+
+// How far in front of the shooter projectiles and beams start, so they clear its own collider
+const MUZZLE_DISTANCE: f32 = 7.0;
+const BEAM_TRACE_SECONDS: f32 = 0.15;
+const BEAM_COLOR: Color = Color::rgb(0.4, 0.9, 1.0);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WeaponKind {
+    Blaster,
+    HomingMissile,
+    SpreadShot,
+    ChargedBeam,
+    PlasmaBolt,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FireMode {
+    // `count` projectiles fanned out evenly across `spread_degrees`
+    Projectiles {
+        speed: f32,
+        count: u32,
+        spread_degrees: f32,
+        scale: f32,
+        lifetime: f32,
+        max_range: f32,
+    },
+    // Steers toward the nearest enemy within `range`
+    Homing {
+        speed: f32,
+        turn_rate: f32,
+        range: f32,
+        scale: f32,
+        lifetime: f32,
+        max_range: f32,
+    },
+    // Charges while fire is held and hits the first thing along a raycast when released
+    Beam {
+        range: f32,
+        charge_seconds: f32,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct WeaponStats {
+    pub kind: WeaponKind,
+    pub name: String,
+    pub cooldown: f32,
+    pub ammo: Option<u32>,
+    // Per projectile, or per beam shot
+    pub damage: f32,
+    #[serde(default)]
+    pub damage_kind: DamageKind,
+    pub fire: FireMode,
+}
+
+// Every weapon in the game, from `assets/config/weapons.ron`
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeaponRegistry {
+    pub weapons: Vec<WeaponStats>,
+    // Carried by enemies, and never selectable by the player
+    #[serde(default)]
+    pub enemy_weapons: Vec<WeaponStats>,
+}
+
+impl Default for WeaponRegistry {
+    fn default() -> Self {
+        parse_embedded(include_str!("../assets/config/weapons.ron"))
+    }
+}
+
+impl RonResource for WeaponRegistry {
+    const PATH: &'static str = "config/weapons.ron";
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.weapons.is_empty() {
+            problems.push("there must be at least one weapon".to_string());
+        }
+        for stats in self.weapons.iter().chain(&self.enemy_weapons) {
+            if !(stats.cooldown.is_finite() && stats.cooldown > 0.0) {
+                problems.push(format!("{}: cooldown must be positive", stats.name));
+            }
+            if let FireMode::Projectiles { count: 0, .. } = stats.fire {
+                problems.push(format!("{}: count must be at least 1", stats.name));
+            }
+            if let FireMode::Projectiles {
+                lifetime,
+                max_range,
+                ..
+            }
+            | FireMode::Homing {
+                lifetime,
+                max_range,
+                ..
+            } = stats.fire
+            {
+                if !(lifetime.is_finite() && lifetime > 0.0) {
+                    problems.push(format!("{}: lifetime must be positive", stats.name));
+                }
+                if !(max_range.is_finite() && max_range > 0.0) {
+                    problems.push(format!("{}: max_range must be positive", stats.name));
+                }
+            }
+        }
+        problems
+    }
+}
+
+impl WeaponRegistry {
+    pub fn get(&self, kind: WeaponKind) -> Option<&WeaponStats> {
+        self.weapons
+            .iter()
+            .chain(&self.enemy_weapons)
+            .find(|stats| stats.kind == kind)
+    }
+}
+
+impl WeaponStats {
+    // `None` for beams, which hit instantly
+    pub fn projectile_speed(&self) -> Option<f32> {
+        match self.fire {
+            FireMode::Projectiles { speed, .. } | FireMode::Homing { speed, .. } => Some(speed),
+            FireMode::Beam { .. } => None,
+        }
+    }
+}
+
+// Anything fired by a weapon. It is despawned once `lifetime` runs out or it has travelled
+// `max_range` from where it was fired, and it never damages `owner`.
+#[derive(Component, Debug)]
+pub struct Projectile {
+    pub owner: Entity,
+    pub lifetime: Timer,
+    pub max_range: f32,
+    pub origin: Vec3,
+}
+
+// One weapon carried by its parent entity, with its own ammo and cooldown
+#[derive(Component, Debug)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    // `None` never runs out
+    pub ammo: Option<u32>,
+    cooldown: Timer,
+    charge: f32,
+}
+
+impl Weapon {
+    pub fn new(stats: &WeaponStats) -> Self {
+        let mut cooldown = Timer::from_seconds(stats.cooldown, TimerMode::Once);
+        // Ready to fire straight away
+        cooldown.tick(cooldown.duration());
+        Self {
+            kind: stats.kind,
+            ammo: stats.ammo,
+            cooldown,
+            charge: 0.0,
+        }
+    }
+
+    // Takes up retuned stats without refilling ammo or restarting the cooldown
+    pub fn restat(&mut self, stats: &WeaponStats) {
+        self.ammo = match (self.ammo, stats.ammo) {
+            (Some(ammo), Some(max)) => Some(ammo.min(max)),
+            (_, max) => max,
+        };
+        self.cooldown
+            .set_duration(Duration::from_secs_f32(stats.cooldown));
+    }
+
+    pub fn ready(&self) -> bool {
+        self.cooldown.finished() && self.ammo != Some(0)
+    }
+
+    pub fn use_round(&mut self) {
+        self.cooldown.reset();
+        if let Some(ammo) = self.ammo.as_mut() {
+            *ammo = ammo.saturating_sub(1);
+        }
+    }
+}
+
+// Which of its `Weapon` children an entity fires
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectedWeapon(pub WeaponKind);
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Homing {
+    pub turn_rate: f32,
+    pub range: f32,
+}
+
+// A fired beam, kept around briefly so it can be drawn
+#[derive(Component, Debug)]
+struct BeamTrace {
+    start: Vec3,
+    end: Vec3,
+    timer: Timer,
+}
+
+pub struct WeaponsPlugin;
+
+impl Plugin for WeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                (
+                    restat_weapons.run_if(resource_changed::<WeaponRegistry>),
+                    tick_weapons,
+                    select_player_weapon,
+                    fire_player_weapon,
+                )
+                    .chain()
+                    .after(PlayerActionsSet)
+                    .in_set(InGameSet::UserInput),
+                (steer_homing_projectiles, fade_beam_traces).in_set(InGameSet::EntityUpdates),
+            ),
+        )
+        .add_systems(
+            Update,
+            draw_beam_traces.run_if(resource_exists::<GizmoConfigStore>),
+        );
+    }
+}
+
+// Gives `entity` one of every registered weapon, with the first one selected
+pub fn equip_all_weapons(commands: &mut Commands, entity: Entity, registry: &WeaponRegistry) {
+    let Some(first) = registry.weapons.first() else {
+        return;
+    };
+    commands
+        .entity(entity)
+        .insert(SelectedWeapon(first.kind))
+        .with_children(|parent| {
+            for stats in &registry.weapons {
+                parent.spawn((Weapon::new(stats), SpatialBundle::default()));
+            }
+        });
+}
+
+// Gives `entity` a single weapon, already selected
+pub fn equip_weapon(commands: &mut Commands, entity: Entity, stats: &WeaponStats) {
+    commands
+        .entity(entity)
+        .insert(SelectedWeapon(stats.kind))
+        .with_children(|parent| {
+            parent.spawn((Weapon::new(stats), SpatialBundle::default()));
+        });
+}
+
+// Rotates `heading` toward `desired` by at most `max_angle` radians. Both must be normalized.
+pub fn turn_toward(heading: Vec3, desired: Vec3, max_angle: f32) -> Vec3 {
+    let angle = heading.angle_between(desired);
+    if angle <= max_angle {
+        desired
+    } else {
+        let axis = heading.cross(desired).try_normalize().unwrap_or(Vec3::Y);
+        Quat::from_axis_angle(axis, max_angle) * heading
+    }
+}
+
+// Applies a hot-reloaded registry to the weapons already equipped
+fn restat_weapons(mut query: Query<&mut Weapon>, registry: Res<WeaponRegistry>) {
+    for mut weapon in query.iter_mut() {
+        if let Some(stats) = registry.get(weapon.kind) {
+            weapon.restat(stats);
+        }
+    }
+}
+
+fn tick_weapons(mut query: Query<&mut Weapon>, time: Res<Time>) {
+    for mut weapon in query.iter_mut() {
+        weapon.cooldown.tick(time.delta());
+    }
+}
+
+fn select_player_weapon(
+    mut query: Query<&mut SelectedWeapon, With<Spaceship>>,
+    actions: Res<PlayerActions>,
+    registry: Res<WeaponRegistry>,
+) {
+    let Ok(mut selected) = query.get_single_mut() else {
+        return;
+    };
+    let Some(slot) = (actions.weapon_slot as usize).checked_sub(1) else {
+        return;
+    };
+    if let Some(stats) = registry.weapons.get(slot) {
+        if selected.0 != stats.kind {
+            info!("Switched to {}", stats.name);
+            selected.0 = stats.kind;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fire_player_weapon(
+    mut commands: Commands,
+    ship_query: Query<
+        (
+            Entity,
+            &Transform,
+            &SelectedWeapon,
+            &Children,
+            &CollisionLayer,
+        ),
+        With<Spaceship>,
+    >,
+    mut weapon_query: Query<&mut Weapon>,
+    layer_query: Query<&CollisionLayer>,
+    layer_table: Res<LayerTable>,
+    actions: Res<PlayerActions>,
+    registry: Res<WeaponRegistry>,
+    archetypes: Res<Archetypes>,
+    scene_assets: Res<SceneAssets>,
+    rapier_context: Res<RapierContext>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    let Ok((ship, transform, selected, children, ship_layer)) = ship_query.get_single() else {
+        return;
+    };
+    let projectile_layer = ship_layer.projectile();
+    let Some(stats) = registry.get(selected.0) else {
+        return;
+    };
+    let Some(mut weapon) = children
+        .iter()
+        .copied()
+        .find(|&child| weapon_query.get(child).is_ok_and(|w| w.kind == selected.0))
+        .and_then(|child| weapon_query.get_mut(child).ok())
+    else {
+        return;
+    };
+
+    match stats.fire {
+        FireMode::Projectiles { .. } | FireMode::Homing { .. } => {
+            if actions.fire && weapon.ready() {
+                spawn_projectiles(
+                    &mut commands,
+                    ship,
+                    transform,
+                    stats,
+                    &archetypes.missile,
+                    &scene_assets,
+                    layer_table.bundle(projectile_layer),
+                );
+                weapon.use_round();
+            }
+        }
+        FireMode::Beam {
+            range,
+            charge_seconds,
+        } => {
+            if actions.fire && weapon.ready() {
+                weapon.charge += time.delta_seconds();
+            } else if weapon.charge > 0.0 {
+                // Released: a full charge fires, anything less fizzles
+                if weapon.charge >= charge_seconds {
+                    let (groups, _) = layer_table.groups(projectile_layer);
+                    let filter = QueryFilter::default()
+                        .exclude_rigid_body(ship)
+                        .groups(groups);
+                    let hit = fire_beam(&mut commands, &rapier_context, filter, transform, range);
+                    // The beam stops at anything on a layer it touches, but only hurts some of them
+                    if let Some(target) = hit.filter(|&target| {
+                        layer_query.get(target).map_or(true, |&target_layer| {
+                            layer_table.deals_damage(projectile_layer, target_layer)
+                        })
+                    }) {
+                        damage_events.send(DamageEvent {
+                            source: ship,
+                            target,
+                            amount: stats.damage,
+                            kind: stats.damage_kind,
+                        });
+                    }
+                    weapon.use_round();
+                }
+                weapon.charge = 0.0;
+            }
+        }
+    }
+}
+
+pub fn spawn_projectiles(
+    commands: &mut Commands,
+    owner: Entity,
+    shooter: &Transform,
+    stats: &WeaponStats,
+    archetype: &EntityArchetype,
+    scene_assets: &SceneAssets,
+    layers: LayerBundle,
+) {
+    let (speed, count, spread, scale, lifetime, max_range, homing) = match stats.fire {
+        FireMode::Projectiles {
+            speed,
+            count,
+            spread_degrees,
+            scale,
+            lifetime,
+            max_range,
+        } => (
+            speed,
+            count,
+            spread_degrees.to_radians(),
+            scale,
+            lifetime,
+            max_range,
+            None,
+        ),
+        FireMode::Homing {
+            speed,
+            turn_rate,
+            range,
+            scale,
+            lifetime,
+            max_range,
+        } => (
+            speed,
+            1,
+            0.0,
+            scale,
+            lifetime,
+            max_range,
+            Some(Homing { turn_rate, range }),
+        ),
+        FireMode::Beam { .. } => return,
+    };
+    let forward = -*shooter.forward();
+    for i in 0..count {
+        // Evenly spaced from -spread/2 to +spread/2
+        let angle = if count > 1 {
+            spread * (i as f32 / (count - 1) as f32 - 0.5)
+        } else {
+            0.0
+        };
+        let direction = Quat::from_rotation_y(angle) * forward;
+        let origin = shooter.translation + direction * MUZZLE_DISTANCE;
+        let mut projectile = commands.spawn((
+            SceneBundle {
+                scene: scene_assets.missiles.clone(),
+                transform: Transform::from_translation(origin)
+                    .with_scale(Vec3::splat(archetype.scale.value() * scale)),
+                ..default()
+            },
+            archetype.collider.collider(),
+            ColliderMassProperties::Density(archetype.density.value()),
+            ReadMassProperties::default(),
+            Velocity {
+                linvel: direction * speed,
+                angvel: Vec3::ZERO,
+            },
+            layers,
+            ArchetypeKind::Missile,
+            Projectile {
+                owner,
+                lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+                max_range,
+                origin,
+            },
+            Health::new(archetype.health),
+            CollisionDamage::flat(stats.damage, stats.damage_kind),
+            archetype.resistances,
+            ActiveEvents::COLLISION_EVENTS,
+            DebugEntity,
+            RigidBody::Dynamic,
+            ExternalForce::default(),
+        ));
+        if let Some(homing) = homing {
+            projectile.insert(homing);
+        }
+        if layers.0 == CollisionLayer::PlayerProjectile {
+            projectile.insert(SpaceshipMissile);
+        }
+    }
+}
+
+// Draws the beam up to the first thing `filter` lets it hit, and returns that thing
+fn fire_beam(
+    commands: &mut Commands,
+    rapier_context: &RapierContext,
+    filter: QueryFilter,
+    transform: &Transform,
+    range: f32,
+) -> Option<Entity> {
+    let direction = -*transform.forward();
+    let start = transform.translation + direction * MUZZLE_DISTANCE;
+    let hit = rapier_context.cast_ray(start, direction, range, true, filter);
+    let distance = hit.map_or(range, |(_, distance)| distance);
+    commands.spawn(BeamTrace {
+        start,
+        end: start + direction * distance,
+        timer: Timer::from_seconds(BEAM_TRACE_SECONDS, TimerMode::Once),
+    });
+    hit.map(|(target, _)| target)
+}
+
+// Turns each homing projectile toward the nearest enemy, at most `turn_rate` radians a second
+fn steer_homing_projectiles(
+    mut query: Query<(&Transform, &mut Velocity, &Homing)>,
+    enemy_query: Query<(), With<Enemy>>,
+    grid: Res<SpatialGrid>,
+    time: Res<Time>,
+) {
+    for (transform, mut velocity, homing) in query.iter_mut() {
+        let position = transform.translation;
+        let Some(target) = grid
+            .within(position, homing.range)
+            .filter(|(entity, _)| enemy_query.contains(*entity))
+            .map(|(_, target)| target)
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+        else {
+            continue;
+        };
+        let speed = velocity.linvel.length();
+        let (Some(heading), Some(desired)) = (
+            velocity.linvel.try_normalize(),
+            Vec3::new(target.x - position.x, 0.0, target.z - position.z).try_normalize(),
+        ) else {
+            continue;
+        };
+        let max_turn = (homing.turn_rate * time.delta_seconds()).min(PI);
+        velocity.linvel = turn_toward(heading, desired, max_turn) * speed;
+    }
+}
+
+fn fade_beam_traces(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut BeamTrace)>,
+    time: Res<Time>,
+) {
+    for (entity, mut trace) in query.iter_mut() {
+        if trace.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn draw_beam_traces(mut gizmos: Gizmos, query: Query<&BeamTrace>) {
+    for trace in query.iter() {
+        gizmos.line(trace.start, trace.end, BEAM_COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restat_keeps_remaining_ammo_within_the_new_limit() {
+        let registry = WeaponRegistry::default();
+        let mut stats = registry.weapons[0].clone();
+        stats.ammo = Some(10);
+        let mut weapon = Weapon::new(&stats);
+        weapon.use_round();
+
+        stats.ammo = Some(5);
+        stats.cooldown *= 2.0;
+        weapon.restat(&stats);
+        assert_eq!(weapon.ammo, Some(5));
+        assert_eq!(weapon.cooldown.duration().as_secs_f32(), stats.cooldown);
+
+        stats.ammo = Some(20);
+        weapon.restat(&stats);
+        assert_eq!(weapon.ammo, Some(5));
+
+        stats.ammo = None;
+        weapon.restat(&stats);
+        assert_eq!(weapon.ammo, None);
+    }
+}
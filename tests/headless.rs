@@ -0,0 +1,372 @@
+use std::{cmp::Ordering, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use jumpy::{
+    archetype::{ArchetypeKind, Archetypes},
+    asteroids::Asteroid,
+    damage::{DamageEvent, DamageKind, LastHit},
+    despawn::Killed,
+    headless::{headless_app, headless_app_from_replay, headless_app_with_seed, step},
+    health::Health,
+    physics::{InterpolatedTransform, FIXED_TIMESTEP},
+    planet::Planet,
+    player_input::{InputSource, PlayerActions},
+    replay::{Replay, ReplayPlayback, ReplayRecorder},
+    rng::GameRng,
+    score::{Score, ScoreTable},
+    settings::Settings,
+    shield::Shield,
+    sound::{AudioTable, MusicLayer, MusicMix, NullAudioLog, Sfx, SoundCue},
+    spaceship::Spaceship,
+    state::{GameState, ResetRun, RunSeed},
+    waves::WavePhase,
+};
+
+fn spaceship(app: &mut App) -> Entity {
+    app.world
+        .query_filtered::<Entity, With<Spaceship>>()
+        .single(&app.world)
+}
+
+fn spaceship_transform(app: &mut App) -> Transform {
+    let ship = spaceship(app);
+    *app.world.get::<Transform>(ship).unwrap()
+}
+
+fn game_state(app: &App) -> GameState {
+    *app.world.resource::<State<GameState>>().get()
+}
+
+#[test]
+fn world_is_spawned_on_first_update() {
+    let mut app = headless_app();
+    step(&mut app, 1);
+
+    assert_eq!(game_state(&app), GameState::InGame);
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<Spaceship>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    assert!(
+        app.world
+            .query_filtered::<(), With<Planet>>()
+            .iter(&app.world)
+            .count()
+            > 0
+    );
+}
+
+#[test]
+fn damage_event_lowers_health() {
+    let mut app = headless_app();
+    step(&mut app, 1);
+    let ship = spaceship(&mut app);
+    let before = app.world.get::<Health>(ship).unwrap().value;
+
+    app.world.send_event(DamageEvent {
+        source: ship,
+        target: ship,
+        amount: 10.0,
+        kind: DamageKind::Kinetic,
+    });
+    step(&mut app, 1);
+
+    assert!(app.world.get::<Health>(ship).unwrap().value < before);
+}
+
+#[test]
+fn player_kills_add_to_the_score() {
+    let mut app = headless_app();
+    step(&mut app, 1);
+    let before = app.world.resource::<Score>().kill_points;
+    app.world.send_event(Killed {
+        entity: Entity::PLACEHOLDER,
+        entity_kind: ArchetypeKind::Enemy,
+        position: Vec3::ZERO,
+        by: Some(LastHit {
+            attacker: ArchetypeKind::Spaceship,
+            damage_kind: DamageKind::Kinetic,
+        }),
+    });
+    step(&mut app, 1);
+
+    let score = app.world.resource::<Score>();
+    let points = app.world.resource::<ScoreTable>().points[&ArchetypeKind::Enemy];
+    assert_eq!(score.kill_points, before + points);
+    assert_eq!(score.kills[&ArchetypeKind::Enemy], 1);
+    assert_eq!(score.combo, 1);
+}
+
+#[test]
+fn destroyed_ship_ends_the_run() {
+    let mut app = headless_app();
+    step(&mut app, 1);
+    let ship = spaceship(&mut app);
+
+    app.world.get_mut::<Health>(ship).unwrap().value = 0.0;
+    step(&mut app, 3);
+
+    assert_eq!(game_state(&app), GameState::GameOver);
+}
+
+#[test]
+fn replay_reproduces_recorded_run() {
+    const SEED: u64 = 42;
+    const FRAMES: usize = 120;
+    let path = std::env::temp_dir().join(format!("jumpy-test-{}.replay", std::process::id()));
+
+    let mut app = headless_app_with_seed(SEED);
+    app.insert_resource(ReplayRecorder::create(&path, SEED).unwrap());
+    for frame in 0..FRAMES {
+        let mut keyboard = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        keyboard.press(KeyCode::KeyW);
+        if frame % 40 < 20 {
+            keyboard.press(KeyCode::KeyD);
+        } else {
+            keyboard.release(KeyCode::KeyD);
+        }
+        step(&mut app, 1);
+    }
+    let recorded = spaceship_transform(&mut app);
+    // Closes the file
+    app.world.remove_resource::<ReplayRecorder>();
+
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(replay.seed, SEED);
+    let mut playback = headless_app_from_replay(replay);
+    step(&mut playback, FRAMES);
+    let played_back = spaceship_transform(&mut playback);
+
+    // The inputs have to have made a difference for the comparison to mean anything
+    let mut idle = headless_app_with_seed(SEED);
+    step(&mut idle, FRAMES);
+    assert_ne!(recorded, spaceship_transform(&mut idle));
+    assert_eq!(recorded, played_back);
+}
+
+// Entities are numbered differently after a reset, so they are matched up by where they are
+fn asteroid_positions(app: &mut App) -> Vec<[f32; 3]> {
+    let mut positions: Vec<_> = app
+        .world
+        .query_filtered::<&Transform, With<Asteroid>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation.to_array())
+        .collect();
+    positions.sort_by(|a, b| {
+        a.iter()
+            .zip(b)
+            .fold(Ordering::Equal, |order, (a, b)| order.then(a.total_cmp(b)))
+    });
+    positions
+}
+
+#[test]
+fn retry_plays_the_replay_again() {
+    // Long enough for the first asteroids to spawn
+    const FRAMES: usize = 150;
+    let forward = PlayerActions {
+        forward: true,
+        ..default()
+    };
+    let mut app = headless_app_from_replay(Replay {
+        seed: 7,
+        frames: vec![forward; FRAMES],
+    });
+    step(&mut app, FRAMES);
+    let first = spaceship_transform(&mut app);
+    let first_asteroids = asteroid_positions(&mut app);
+    assert!(!first_asteroids.is_empty());
+    step(&mut app, 10);
+    assert_eq!(*app.world.resource::<InputSource>(), InputSource::Keyboard);
+
+    app.world.send_event(ResetRun {
+        seed: RunSeed::Same,
+        then: GameState::InGame,
+    });
+    step(&mut app, 1);
+    assert_eq!(*app.world.resource::<InputSource>(), InputSource::Playback);
+    // The reset took the place of the first update of the original run
+    step(&mut app, FRAMES - 1);
+    assert_eq!(spaceship_transform(&mut app), first);
+    assert_eq!(asteroid_positions(&mut app), first_asteroids);
+
+    app.world.send_event(ResetRun {
+        seed: RunSeed::New,
+        then: GameState::InGame,
+    });
+    step(&mut app, 1);
+    assert_eq!(*app.world.resource::<InputSource>(), InputSource::Keyboard);
+    assert!(!app.world.contains_resource::<ReplayPlayback>());
+}
+
+#[test]
+fn reset_starts_a_new_recording() {
+    let path = std::env::temp_dir().join(format!("jumpy-reset-{}.replay", std::process::id()));
+    let mut app = headless_app_with_seed(3);
+    app.insert_resource(ReplayRecorder::create(&path, 3).unwrap());
+    step(&mut app, 30);
+    app.world.send_event(ResetRun {
+        seed: RunSeed::New,
+        then: GameState::InGame,
+    });
+    step(&mut app, 10);
+    app.world.remove_resource::<ReplayRecorder>();
+
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(replay.seed, app.world.resource::<GameRng>().seed());
+    assert_ne!(replay.seed, 3);
+    assert!(replay.frames.len() <= 10, "{}", replay.frames.len());
+}
+
+// Where a cue is heard at full volume
+fn listener_position(app: &mut App) -> Vec3 {
+    app.world
+        .query_filtered::<&GlobalTransform, With<Camera>>()
+        .iter(&app.world)
+        .next()
+        .map_or(Vec3::ZERO, GlobalTransform::translation)
+}
+
+fn shield_voices(app: &App) -> Vec<f32> {
+    app.world
+        .resource::<NullAudioLog>()
+        .voices
+        .iter()
+        .filter(|voice| voice.sfx == Sfx::ShieldUp)
+        .map(|voice| voice.gain)
+        .collect()
+}
+
+#[test]
+fn sound_cues_are_mixed_and_deduplicated() {
+    let mut app = headless_app();
+    step(&mut app, 1);
+    let position = listener_position(&mut app);
+    let cue = SoundCue {
+        sfx: Sfx::ShieldUp,
+        position,
+    };
+
+    // Two of the same cue in one frame are heard once
+    app.world.send_event(cue);
+    app.world.send_event(cue);
+    step(&mut app, 1);
+    let table = app.world.resource::<AudioTable>();
+    let expected = app.world.resource::<Settings>().volume.gain()
+        * table.sfx_volume
+        * table.sfx(Sfx::ShieldUp).gain;
+    assert_eq!(shield_voices(&app), vec![expected]);
+
+    // Still inside `min_interval`
+    app.world.send_event(cue);
+    step(&mut app, 1);
+    assert_eq!(shield_voices(&app).len(), 1);
+
+    let min_interval = app
+        .world
+        .resource::<AudioTable>()
+        .sfx(Sfx::ShieldUp)
+        .min_interval;
+    step(
+        &mut app,
+        (min_interval as f64 / FIXED_TIMESTEP).ceil() as usize,
+    );
+    app.world.send_event(cue);
+    step(&mut app, 1);
+    assert_eq!(shield_voices(&app).len(), 2);
+}
+
+#[test]
+fn music_fades_to_the_game_over_layers() {
+    let mut app = headless_app();
+    step(&mut app, 120);
+    let mix = app.world.resource::<MusicMix>();
+    assert_eq!(mix.level(MusicLayer::Pulse), 1.0);
+    assert_eq!(mix.level(MusicLayer::Lament), 0.0);
+
+    let ship = spaceship(&mut app);
+    app.world.get_mut::<Health>(ship).unwrap().value = 0.0;
+    step(&mut app, 10);
+    assert_eq!(game_state(&app), GameState::GameOver);
+    let mix = app.world.resource::<MusicMix>();
+    let (pulse, lament) = (mix.level(MusicLayer::Pulse), mix.level(MusicLayer::Lament));
+    assert!(pulse < 1.0 && lament > 0.0, "{} {}", pulse, lament);
+
+    step(&mut app, 120);
+    let mix = app.world.resource::<MusicMix>();
+    for layer in MusicLayer::ALL {
+        let target = layer.target(GameState::GameOver, WavePhase::Intermission);
+        assert_eq!(mix.level(layer), target, "{:?}", layer);
+    }
+}
+
+// Every rigid body's pose after each fixed step
+#[derive(Resource, Default)]
+struct FixedStepPoses(Vec<Vec<(Entity, Transform)>>);
+
+fn record_fixed_step_poses(
+    mut poses: ResMut<FixedStepPoses>,
+    query: Query<(Entity, &Transform), With<InterpolatedTransform>>,
+) {
+    let mut step: Vec<_> = query
+        .iter()
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+    step.sort_by_key(|(entity, _)| *entity);
+    poses.0.push(step);
+}
+
+fn run_at_frame_rate(fps: u32, seconds: u32) -> Vec<Vec<(Entity, Transform)>> {
+    let mut app = headless_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / fps as f64,
+    )))
+    .init_resource::<FixedStepPoses>()
+    .add_systems(
+        FixedLast,
+        record_fixed_step_poses.run_if(in_state(GameState::InGame)),
+    );
+    let mut keyboard = app.world.resource_mut::<ButtonInput<KeyCode>>();
+    keyboard.press(KeyCode::KeyW);
+    keyboard.press(KeyCode::KeyD);
+    step(&mut app, (fps * seconds) as usize);
+    app.world.remove_resource::<FixedStepPoses>().unwrap().0
+}
+
+#[test]
+fn simulation_does_not_depend_on_frame_rate() {
+    let slow = run_at_frame_rate(30, 2);
+    let fast = run_at_frame_rate(240, 2);
+
+    // Frame times don't divide into whole nanoseconds, so the two runs can end a step apart
+    let steps = slow.len().min(fast.len());
+    assert!(steps >= 110, "only {} fixed steps ran", steps);
+    assert!(!slow[steps - 1].is_empty());
+    for step in 0..steps {
+        assert!(
+            slow[step] == fast[step],
+            "poses differ after fixed step {}",
+            step
+        );
+    }
+}
+
+#[test]
+fn retuned_shield_applies_to_the_current_ship() {
+    let mut app = headless_app();
+    step(&mut app, 1);
+    let mut stats = app.world.resource::<Archetypes>().spaceship.shield.unwrap();
+    stats.capacity /= 2.0;
+    app.world.resource_mut::<Archetypes>().spaceship.shield = Some(stats);
+    step(&mut app, 1);
+
+    let ship = spaceship(&mut app);
+    let shield = app.world.get::<Shield>(ship).unwrap();
+    assert_eq!(shield.stats, stats);
+    assert!(shield.energy <= stats.capacity);
+}